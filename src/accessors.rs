@@ -0,0 +1,305 @@
+//! Access to config keys by name, and typed accessors for keys that don't
+//! have a field on `Npmrc`.
+
+use std::borrow::Cow;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use definitions::definition;
+use env::normalize_key;
+use Npmrc;
+
+impl Npmrc {
+    /// The location of npm's cache directory.
+    pub fn cache(&self) -> PathBuf {
+        self.path("cache").unwrap_or_default()
+    }
+
+    /// The prefix global packages are installed into: `prefix` if it's set,
+    /// otherwise the one npm derives from the Node.js location, if known.
+    pub fn prefix(&self) -> Option<PathBuf> {
+        self.path("prefix").or_else(|| self.context.prefix.clone())
+    }
+
+    /// Whether registry TLS certificates are validated.
+    pub fn strict_ssl(&self) -> bool {
+        self.flag("strict-ssl")
+    }
+
+    /// A file of trusted PEM certificates.
+    pub fn cafile(&self) -> Option<PathBuf> {
+        self.path("cafile")
+    }
+
+    /// Trusted PEM certificates given inline.
+    pub fn ca(&self) -> Vec<String> {
+        self.list("ca")
+    }
+
+    /// The proxy for outgoing HTTP requests.
+    pub fn proxy(&self) -> Option<String> {
+        self.string("proxy")
+    }
+
+    /// The proxy for outgoing HTTPS requests.
+    pub fn https_proxy(&self) -> Option<String> {
+        self.string("https-proxy")
+    }
+
+    /// Domains that bypass the proxy.
+    pub fn noproxy(&self) -> Vec<String> {
+        self.list("noproxy")
+            .iter()
+            .flat_map(|value| value.split(','))
+            .map(str::trim)
+            .filter(|domain| !domain.is_empty())
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// How many times to retry a failed registry request.
+    pub fn fetch_retries(&self) -> u32 {
+        self.number("fetch-retries")
+    }
+
+    /// The exponential backoff factor between retries.
+    pub fn fetch_retry_factor(&self) -> u32 {
+        self.number("fetch-retry-factor")
+    }
+
+    /// The shortest to wait between retries, in milliseconds.
+    pub fn fetch_retry_mintimeout(&self) -> u64 {
+        self.number("fetch-retry-mintimeout")
+    }
+
+    /// The longest to wait between retries, in milliseconds.
+    pub fn fetch_retry_maxtimeout(&self) -> u64 {
+        self.number("fetch-retry-maxtimeout")
+    }
+
+    /// How long to wait for a registry response, in milliseconds.
+    pub fn fetch_timeout(&self) -> u64 {
+        self.number("fetch-timeout")
+    }
+
+    /// The most connections to keep open per origin.
+    pub fn maxsockets(&self) -> u32 {
+        self.number("maxsockets")
+    }
+
+    /// Whether scripts from package.json files are skipped.
+    pub fn ignore_scripts(&self) -> bool {
+        self.flag("ignore-scripts")
+    }
+
+    /// Whether packages that don't support the current Node.js are refused.
+    pub fn engine_strict(&self) -> bool {
+        self.flag("engine-strict")
+    }
+
+    /// Whether exact versions are saved instead of ranges.
+    pub fn save_exact(&self) -> bool {
+        self.flag("save-exact")
+    }
+
+    /// The range operator saved versions are prefixed with.
+    pub fn save_prefix(&self) -> String {
+        self.string("save-prefix").unwrap_or_default()
+    }
+
+    /// Dependency types left out of node_modules.
+    pub fn omit(&self) -> Vec<String> {
+        self.list("omit")
+    }
+
+    /// Dependency types to install, overriding `omit`.
+    pub fn include(&self) -> Vec<String> {
+        self.list("include")
+    }
+
+    /// Whether peer dependencies are ignored, like npm 3 through 6.
+    pub fn legacy_peer_deps(&self) -> bool {
+        self.flag("legacy-peer-deps")
+    }
+
+    /// Whether conflicting peer dependencies fail the install.
+    pub fn strict_peer_deps(&self) -> bool {
+        self.flag("strict-peer-deps")
+    }
+
+    /// The minimum vulnerability level that makes `npm audit` fail.
+    pub fn audit_level(&self) -> Option<String> {
+        self.string("audit-level")
+    }
+
+    /// Whether audit reports are submitted alongside installs.
+    pub fn audit(&self) -> bool {
+        self.flag("audit")
+    }
+
+    /// Whether cached data is used without revalidating it.
+    pub fn prefer_offline(&self) -> bool {
+        self.flag("prefer-offline")
+    }
+
+    /// Whether cached data is always revalidated.
+    pub fn prefer_online(&self) -> bool {
+        self.flag("prefer-online")
+    }
+
+    /// Whether npm stays off the network entirely.
+    pub fn offline(&self) -> bool {
+        self.flag("offline")
+    }
+
+    /// The dist-tag installs and publishes use.
+    pub fn tag(&self) -> String {
+        self.string("tag").unwrap_or_default()
+    }
+
+    /// The file mode mask for new files and directories.
+    pub fn umask(&self) -> u32 {
+        self.number("umask")
+    }
+
+    /// Patterns of dependencies hoisted to the root of node_modules, as pnpm
+    /// reads them from `public-hoist-pattern[]=`.
+    pub fn public_hoist_pattern(&self) -> Vec<String> {
+        self.list("public-hoist-pattern")
+    }
+
+    /// The user config file.
+    pub fn userconfig(&self) -> Option<PathBuf> {
+        self.path("userconfig")
+    }
+
+    /// The global config file: `globalconfig` if it's set, otherwise
+    /// `etc/npmrc` in the prefix.
+    pub fn globalconfig(&self) -> Option<PathBuf> {
+        self.path("globalconfig")
+            .or_else(|| self.prefix().map(|prefix| prefix.join("etc").join("npmrc")))
+    }
+
+    /// The value of `key` as configured, or `None` if it isn't set.
+    ///
+    /// This reaches every key, including those with a field on `Npmrc` and
+    /// credentials like `//registry.npmjs.org/:_authToken`, which are
+    /// returned as stored. Values are coerced like they are for npm: paths
+    /// are absolute and booleans are `true` or `false`. For a list, this is
+    /// the last value; `list` has all of them. npm's defaults aren't
+    /// included, see `definition` for those.
+    ///
+    /// Keys are looked up as written, then spelled the way npm spells flags
+    /// and environment variables: `fetch_retries`, `FETCH-RETRIES` and
+    /// `fetch-retries[]` all find `fetch-retries`.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.lists
+            .get(&*self.lookup_key(key))
+            .and_then(|values| values.last())
+            .map(String::as_str)
+    }
+
+    /// The value of `key` parsed as a `T`, like `get`.
+    ///
+    /// ```
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let npmrc: npmrc::Npmrc = "fetch-retries=5\nloglevel=warn".parse()?;
+    /// let retries: Option<u32> = npmrc.get_typed("fetch-retries")?;
+    /// let loglevel = npmrc.get_typed::<npmrc::LogLevel>("loglevel")?;
+    /// assert_eq!(retries, Some(5));
+    /// assert_eq!(loglevel, Some(npmrc::LogLevel::Warn));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_typed<T: FromStr>(&self, key: &str) -> Result<Option<T>, T::Err> {
+        self.get(key).map(str::parse).transpose()
+    }
+
+    /// Whether `key` is assigned in any layer, looked up like `get`.
+    ///
+    /// This is also true for a key that's unset with `null`, or with `false`
+    /// for the proxy keys, in which case `get` returns `None`.
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.sources.contains_key(&*self.lookup_key(key))
+    }
+
+    /// Every key assigned in any layer, sorted.
+    ///
+    /// Like `contains_key`, this includes keys that are unset; `iter` has the
+    /// keys with a value, along with their values.
+    pub fn keys(&self) -> Vec<&str> {
+        let mut keys: Vec<&str> = self.sources.keys().map(String::as_str).collect();
+        keys.sort();
+        keys
+    }
+
+    /// Every configured key and value, sorted by key, with a pair for each
+    /// value of a list.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> + '_ {
+        let mut keys: Vec<&String> = self.lists.keys().collect();
+        keys.sort();
+        keys.into_iter().flat_map(move |key| {
+            self.lists[key]
+                .iter()
+                .map(move |value| (key.as_str(), value.as_str()))
+        })
+    }
+
+    // `key` as it's stored: as written if it's assigned, otherwise spelled
+    // like npm spells flags. Nerf-darted and scoped keys are kept as written.
+    fn lookup_key<'a>(&self, key: &'a str) -> Cow<'a, str> {
+        if self.sources.contains_key(key) || key.starts_with("//") || key.starts_with('@') {
+            return Cow::Borrowed(key);
+        }
+        let key = key.strip_suffix("[]").unwrap_or(key);
+        Cow::Owned(normalize_key(key))
+    }
+
+    /// Every value of a list key, in order, looked up like `get`.
+    ///
+    /// Lists are written as repeated `key[]=value` lines. A key set once with
+    /// `key=value` is a list of one.
+    pub fn list(&self, key: &str) -> Vec<String> {
+        let key = self.lookup_key(key);
+        match self.lists.get(&*key) {
+            Some(values) => values.clone(),
+            None => self.raw(&key).map(str::to_string).into_iter().collect(),
+        }
+    }
+
+    // The configured value of `key`, or its default.
+    fn raw(&self, key: &str) -> Option<&str> {
+        self.other
+            .get(key)
+            .map(String::as_str)
+            .or_else(|| definition(key).and_then(|definition| definition.default))
+    }
+
+    fn string(&self, key: &str) -> Option<String> {
+        self.raw(key).map(str::to_string)
+    }
+
+    fn flag(&self, key: &str) -> bool {
+        self.raw(key) == Some("true")
+    }
+
+    // A value that doesn't fit `T`, like `fetch-retries=2.5`, falls back to
+    // npm's default rather than zero.
+    fn number<T: FromStr + Default>(&self, key: &str) -> T {
+        let default = || definition(key).and_then(|definition| definition.default);
+        self.raw(key)
+            .and_then(|value| value.parse().ok())
+            .or_else(|| default()?.parse().ok())
+            .unwrap_or_default()
+    }
+
+    // Configured paths are resolved while loading, so only defaults can
+    // still start with `~/`.
+    fn path(&self, key: &str) -> Option<PathBuf> {
+        let value = self.raw(key)?;
+        match (value.strip_prefix("~/"), self.context.home.as_ref()) {
+            (Some(rest), Some(home)) => Some(home.join(rest)),
+            _ => Some(PathBuf::from(value)),
+        }
+    }
+}
@@ -0,0 +1,362 @@
+//! Per-registry credentials, keyed by nerf dart.
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use url::Url;
+
+use {Npmrc, Secret};
+
+/// The fields npm reads from nerf-darted `//registry/:field` keys.
+pub(crate) const FIELDS: &[&str] = &[
+    "_auth",
+    "_authToken",
+    "_password",
+    "always-auth",
+    "certfile",
+    "email",
+    "keyfile",
+    "username",
+];
+
+/// Credentials configured for a single registry.
+///
+/// npm scopes credentials to a registry by prefixing the key with the
+/// registry's "nerf dart", its URL without the protocol:
+///
+/// ```ini
+/// //npm.pkg.github.com/org/:_authToken=ghp_...
+/// //registry.example.com/:username=me
+/// //registry.example.com/:_password=c2VjcmV0
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RegistryCredentials {
+    /// The nerf dart the credentials are configured for, e.g.
+    /// `//registry.npmjs.org/`.
+    pub registry: String,
+
+    /// The `_authToken`, sent as a bearer token.
+    pub token: Option<Secret>,
+
+    /// The user part of a decoded `_auth`, or the `username`.
+    pub username: Option<String>,
+
+    /// The password part of a decoded `_auth`, or the `_password` decoded
+    /// from base64.
+    pub password: Option<Secret>,
+
+    /// The `email` associated with the account.
+    pub email: Option<String>,
+
+    /// Path to a client certificate for the registry.
+    pub certfile: Option<PathBuf>,
+
+    /// Path to the client certificate's private key.
+    pub keyfile: Option<PathBuf>,
+}
+
+impl RegistryCredentials {
+    // Build credentials from the `//registry/:field` values set for one
+    // registry, or `None` if none of them are credentials.
+    fn from_fields(registry: &str, fields: &BTreeMap<&str, &str>) -> Option<Self> {
+        let get = |field| fields.get(field).map(|value: &&str| value.to_string());
+        let mut creds = RegistryCredentials {
+            registry: registry.to_string(),
+            token: get("_authToken").map(Secret::from),
+            username: get("username"),
+            password: fields
+                .get("_password")
+                .and_then(|value| decode(value))
+                .map(Secret::from),
+            email: get("email"),
+            certfile: get("certfile").map(PathBuf::from),
+            keyfile: get("keyfile").map(PathBuf::from),
+        };
+
+        // `_auth` holds base64 `username:password`. Like npm-registry-fetch,
+        // it takes precedence over a separate `username` and `_password`.
+        if let Some(auth) = fields.get("_auth").and_then(|value| decode(value)) {
+            let mut parts = auth.splitn(2, ':');
+            creds.username = parts.next().map(str::to_string);
+            creds.password = parts.next().map(Secret::from);
+        }
+
+        if creds == RegistryCredentials::new(registry) {
+            None
+        } else {
+            Some(creds)
+        }
+    }
+
+    fn new(registry: &str) -> Self {
+        RegistryCredentials {
+            registry: registry.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// The `Authorization` header value these credentials produce.
+    ///
+    /// A token is sent as `Bearer`, and takes precedence over a username and
+    /// password, which are sent as `Basic`. Like npm, the username and
+    /// password come from `_auth` when it's set, and otherwise from
+    /// `username` and `_password`.
+    pub fn authorization(&self) -> Option<String> {
+        if let Some(ref token) = self.token {
+            return Some(format!("Bearer {}", token.expose()));
+        }
+
+        match (&self.username, &self.password) {
+            (Some(username), Some(password)) => {
+                let encoded = BASE64.encode(format!("{}:{}", username, password.expose()));
+                Some(format!("Basic {}", encoded))
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether these credentials can authenticate with the registry, using
+    /// the same rules npm uses to decide whether a nerf dart has auth.
+    pub fn has_auth(&self) -> bool {
+        self.token.is_some()
+            || (self.username.is_some() && self.password.is_some())
+            || (self.certfile.is_some() && self.keyfile.is_some())
+    }
+}
+
+/// Compute npm's nerf dart for a URL: its host and directory, without the
+/// protocol, query or file name.
+///
+/// `https://registry.npmjs.org/foo/-/foo-1.0.0.tgz` becomes
+/// `//registry.npmjs.org/foo/-/`.
+pub fn nerf_dart(url: &str) -> Option<String> {
+    let url = Url::parse(url).ok()?.join(".").ok()?;
+    without_protocol(&url)
+}
+
+// `//host:port/path` for a URL.
+fn without_protocol(url: &Url) -> Option<String> {
+    let host = url.host_str()?;
+    Some(match url.port() {
+        Some(port) => format!("//{}:{}{}", host, port, url.path()),
+        None => format!("//{}{}", host, url.path()),
+    })
+}
+
+// Decode a base64 value, treating invalid encodings as unset.
+fn decode(value: &str) -> Option<String> {
+    let bytes = BASE64.decode(value.trim()).ok()?;
+    String::from_utf8(bytes).ok()
+}
+
+// Split a nerf-darted key like `//host/path/:_authToken` into its registry
+// and key.
+pub(crate) fn split_key(key: &str) -> Option<(&str, &str)> {
+    if !key.starts_with("//") {
+        return None;
+    }
+    let colon = key.rfind(':')?;
+    Some((&key[..colon], &key[colon + 1..]))
+}
+
+impl Npmrc {
+    /// All registry credentials in the config, keyed by nerf dart.
+    pub fn credentials(&self) -> BTreeMap<String, RegistryCredentials> {
+        let mut fields: BTreeMap<&str, BTreeMap<&str, &str>> = BTreeMap::new();
+        for (key, value) in &self.other {
+            if let Some((registry, field)) = split_key(key) {
+                fields.entry(registry).or_default().insert(field, value);
+            }
+        }
+
+        fields
+            .iter()
+            .filter_map(|(registry, fields)| RegistryCredentials::from_fields(registry, fields))
+            .map(|creds| (creds.registry.clone(), creds))
+            .collect()
+    }
+
+    /// The credentials npm would use for a request to `url`.
+    ///
+    /// Like npm, this walks up the URL's path one segment at a time and
+    /// returns the first, most specific, registry with auth configured.
+    pub fn credentials_for(&self, url: &str) -> Option<RegistryCredentials> {
+        let mut key = without_protocol(&Url::parse(url).ok()?)?;
+
+        let mut credentials = self.credentials();
+        while key.len() > 2 {
+            if credentials
+                .get(&key)
+                .is_some_and(RegistryCredentials::has_auth)
+            {
+                return credentials.remove(&key);
+            }
+
+            // Drop either the trailing slash or the last path segment, so
+            // both `//host/path/:key` and `//host/path:key` are tried.
+            if key.ends_with('/') {
+                key.pop();
+            } else {
+                let slash = key.rfind('/').unwrap();
+                key.truncate(slash + 1);
+            }
+        }
+
+        None
+    }
+
+    /// The `Authorization` header npm would send with a request to `url`.
+    ///
+    /// Credentials configured for the URL itself are always used. Otherwise
+    /// the default registry's credentials are sent only if the URL is on the
+    /// registry's host, or if `always-auth` is set for the registry, so a
+    /// tarball hosted elsewhere never receives them by accident.
+    pub fn auth_header_for(&self, url: &str) -> Option<String> {
+        let registry = self.default_registry().to_string();
+        self.auth_header(url, &registry)
+    }
+
+    // The `Authorization` header for a request to `url` made on behalf of
+    // `registry`.
+    pub(crate) fn auth_header(&self, url: &str, registry: &str) -> Option<String> {
+        if let Some(header) = self
+            .credentials_for(url)
+            .and_then(|creds| creds.authorization())
+        {
+            return Some(header);
+        }
+
+        let target = Url::parse(url).ok()?;
+        let origin = Url::parse(registry).ok()?;
+        let same_host = target.host_str() == origin.host_str()
+            && target.port_or_known_default() == origin.port_or_known_default();
+
+        if same_host || self.always_auth(registry) {
+            self.credentials_for(registry)
+                .and_then(|creds| creds.authorization())
+        } else {
+            None
+        }
+    }
+
+    // Whether `always-auth` is set for `registry`, or globally.
+    fn always_auth(&self, registry: &str) -> bool {
+        let scoped =
+            nerf_dart(registry).and_then(|nerf| self.other.get(&format!("{}:always-auth", nerf)));
+        scoped
+            .or_else(|| self.other.get("always-auth"))
+            .is_some_and(|value| value == "true")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use PackageSpec;
+
+    fn npmrc(contents: &str) -> Npmrc {
+        contents.parse().unwrap()
+    }
+
+    fn basic(credentials: &str) -> String {
+        format!("Basic {}", BASE64.encode(credentials))
+    }
+
+    #[test]
+    fn sends_registry_credentials_to_its_host() {
+        let npmrc = npmrc("registry=https://r.example/\n//r.example/:_authToken=tok\n");
+        let bearer = Some("Bearer tok".to_string());
+        assert_eq!(npmrc.auth_header_for("https://r.example/pkg"), bearer);
+        assert_eq!(
+            npmrc.auth_header_for("https://r.example/pkg/-/pkg-1.0.0.tgz"),
+            bearer
+        );
+        assert_eq!(npmrc.auth_header_for("http://r.example:8080/pkg"), None);
+    }
+
+    #[test]
+    fn keeps_credentials_from_other_hosts_unless_always_auth() {
+        let config = "registry=https://r.example/\n//r.example/:_authToken=tok\n";
+        let tarball = "https://cdn.example/pkg/-/pkg-1.0.0.tgz";
+        assert_eq!(npmrc(config).auth_header_for(tarball), None);
+
+        let bearer = Some("Bearer tok".to_string());
+        let global = format!("{}always-auth=true\n", config);
+        assert_eq!(npmrc(&global).auth_header_for(tarball), bearer);
+        let scoped = format!("{}//r.example/:always-auth=true\n", config);
+        assert_eq!(npmrc(&scoped).auth_header_for(tarball), bearer);
+    }
+
+    #[test]
+    fn matches_the_longest_registry_path() {
+        let npmrc = npmrc(
+            "//r.example/npm/:_authToken=deep\n\
+             //r.example/:_authToken=root\n\
+             //other.example/npm/:_authToken=other\n",
+        );
+        let token = |url: &str| {
+            npmrc
+                .credentials_for(url)
+                .and_then(|creds| creds.token)
+                .map(|token| token.expose().to_string())
+        };
+        assert_eq!(token("https://r.example/npm/pkg").as_deref(), Some("deep"));
+        assert_eq!(token("https://r.example/npm/").as_deref(), Some("deep"));
+        assert_eq!(token("https://r.example/npmx/pkg").as_deref(), Some("root"));
+        assert_eq!(token("https://r.example/pkg").as_deref(), Some("root"));
+        assert_eq!(
+            token("https://other.example/npm/pkg").as_deref(),
+            Some("other")
+        );
+        assert_eq!(token("https://other.example/npmx/pkg"), None);
+        assert_eq!(token("https://other.example/"), None);
+    }
+
+    #[test]
+    fn auth_takes_precedence_over_username_and_password() {
+        let both = npmrc(&format!(
+            "//r.example/:_auth={}\n//r.example/:username=other\n//r.example/:_password={}\n",
+            BASE64.encode("me:secret"),
+            BASE64.encode("other-secret"),
+        ));
+        let creds = &both.credentials()["//r.example/"];
+        assert_eq!(creds.username.as_deref(), Some("me"));
+        assert_eq!(creds.authorization(), Some(basic("me:secret")));
+
+        let fallback = npmrc(&format!(
+            "//r.example/:username=other\n//r.example/:_password={}\n",
+            BASE64.encode("other-secret"),
+        ));
+        let creds = &fallback.credentials()["//r.example/"];
+        assert_eq!(creds.authorization(), Some(basic("other:other-secret")));
+    }
+
+    #[test]
+    fn token_takes_precedence_over_auth() {
+        let npmrc = npmrc(&format!(
+            "//r.example/:_auth={}\n//r.example/:_authToken=tok\n",
+            BASE64.encode("me:secret"),
+        ));
+        assert_eq!(
+            npmrc.credentials()["//r.example/"].authorization(),
+            Some("Bearer tok".to_string())
+        );
+    }
+
+    #[test]
+    fn scoped_packages_use_their_registry_credentials() {
+        let npmrc = npmrc(
+            "//registry.npmjs.org/:_authToken=public\n\
+             @org:registry=https://npm.org.example/\n\
+             //npm.org.example/:_authToken=org\n",
+        );
+        let header = |spec: &str| {
+            let resolved = npmrc.resolve(&PackageSpec::parse(spec).unwrap()).unwrap();
+            npmrc.auth_header(&resolved.packument_url, &resolved.registry)
+        };
+        assert_eq!(header("@org/pkg@1.0.0"), Some("Bearer org".to_string()));
+        assert_eq!(header("@other/pkg"), Some("Bearer public".to_string()));
+        assert_eq!(header("pkg"), Some("Bearer public".to_string()));
+    }
+}
@@ -0,0 +1,467 @@
+//! The config keys npm understands, with their types and defaults.
+
+/// The type of value a config key holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Type {
+    /// `true` or `false`.
+    Boolean,
+
+    /// Free-form text.
+    String,
+
+    /// A number.
+    Number,
+
+    /// A filesystem path. A leading `~/` refers to the home directory.
+    Path,
+
+    /// An absolute URL.
+    Url,
+
+    /// A file mode mask, written in octal.
+    Umask,
+
+    /// One of a fixed set of values.
+    Enum(&'static [&'static str]),
+}
+
+/// A config key npm knows about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Definition {
+    /// The key, as written in `.npmrc`.
+    pub key: &'static str,
+
+    /// The type of the key's value.
+    pub ty: Type,
+
+    /// Whether the key holds a list of values rather than a single value.
+    pub multiple: bool,
+
+    /// npm's default, as it would be written in `.npmrc`. `None` when the
+    /// key is unset by default or its default is computed at runtime.
+    pub default: Option<&'static str>,
+
+    /// A short description of the key.
+    pub description: &'static str,
+
+    /// Why the key is deprecated and what replaces it, if it is.
+    pub deprecated: Option<&'static str>,
+}
+
+/// Look up the definition of `key`.
+pub fn definition(key: &str) -> Option<&'static Definition> {
+    DEFINITIONS
+        .binary_search_by(|definition| definition.key.cmp(key))
+        .ok()
+        .map(|index| &DEFINITIONS[index])
+}
+
+macro_rules! def {
+    ($key:expr, $ty:expr, $default:expr, $description:expr) => {
+        def!($key, $ty, false, $default, $description, None)
+    };
+    ($key:expr, $ty:expr, $multiple:expr, $default:expr, $description:expr) => {
+        def!($key, $ty, $multiple, $default, $description, None)
+    };
+    ($key:expr, $ty:expr, $multiple:expr, $default:expr, $description:expr, $deprecated:expr) => {
+        Definition {
+            key: $key,
+            ty: $ty,
+            multiple: $multiple,
+            default: $default,
+            description: $description,
+            deprecated: $deprecated,
+        }
+    };
+}
+
+const AUDIT_LEVELS: &[&str] = &["info", "low", "moderate", "high", "critical", "none"];
+const DEP_TYPES: &[&str] = &["dev", "optional", "peer"];
+const LOG_LEVELS: &[&str] = &[
+    "silent", "error", "warn", "notice", "http", "timing", "info", "verbose", "silly",
+];
+
+/// Every config key npm defines, sorted by key.
+#[rustfmt::skip]
+pub static DEFINITIONS: &[Definition] = &[
+    def!("_auth", Type::String, false, None,
+        "Base64 encoded `username:password`. Must be scoped to a registry.",
+        Some("scope it to a registry: //registry.example.com/:_auth")),
+    def!("access", Type::Enum(&["public", "restricted"]), None,
+        "Whether a published scoped package is public or restricted."),
+    def!("all", Type::Boolean, Some("false"),
+        "Show all outdated or installed packages, not just direct dependencies."),
+    def!("allow-same-version", Type::Boolean, Some("false"),
+        "Allow `npm version` to set the version to the current version."),
+    def!("also", Type::Enum(&["dev", "development"]), false, None,
+        "Install dev dependencies as well.",
+        Some("use --include=dev instead")),
+    def!("always-auth", Type::Boolean, false, Some("false"),
+        "Always send authentication, even for tarballs on other hosts.",
+        Some("npm 7 and later ignore it")),
+    def!("audit", Type::Boolean, Some("true"),
+        "Submit audit reports alongside installs."),
+    def!("audit-level", Type::Enum(AUDIT_LEVELS), None,
+        "The minimum vulnerability level that makes `npm audit` fail."),
+    def!("auth-type", Type::Enum(&["legacy", "web"]), Some("web"),
+        "The authentication flow `npm login` uses."),
+    def!("before", Type::String, None,
+        "Only install versions published before this date."),
+    def!("bin-links", Type::Boolean, Some("true"),
+        "Create symlinks or shims for package executables."),
+    def!("browser", Type::String, None,
+        "The browser `npm docs` and friends open."),
+    def!("ca", Type::String, true, None,
+        "PEM certificates trusted for TLS connections to the registry."),
+    def!("cache", Type::Path, Some("~/.npm"),
+        "The location of npm's cache directory."),
+    def!("cache-max", Type::Number, false, None,
+        "Maximum age of cached data.",
+        Some("use --prefer-online instead")),
+    def!("cache-min", Type::Number, false, None,
+        "Minimum age of cached data.",
+        Some("use --prefer-offline instead")),
+    def!("cafile", Type::Path, None,
+        "A file containing one or more trusted PEM certificates."),
+    def!("call", Type::String, Some(""),
+        "A command to run with `npm exec`."),
+    def!("cert", Type::String, false, None,
+        "A client certificate for TLS connections.",
+        Some("use //registry.example.com/:certfile instead")),
+    def!("ci-name", Type::String, None,
+        "The name of the CI system, included in the user agent."),
+    def!("cidr", Type::String, true, None,
+        "CIDR ranges a token made with `npm token create` is limited to."),
+    def!("color", Type::Enum(&["always", "true", "false"]), Some("true"),
+        "Whether to use colored output."),
+    def!("commit-hooks", Type::Boolean, Some("true"),
+        "Run git commit hooks when `npm version` commits."),
+    def!("cpu", Type::String, None,
+        "Override the CPU architecture used to select optional dependencies."),
+    def!("depth", Type::Number, None,
+        "How deep `npm ls` and friends recurse."),
+    def!("description", Type::Boolean, Some("true"),
+        "Show package descriptions in `npm search`."),
+    def!("dev", Type::Boolean, false, Some("false"),
+        "Install dev dependencies.",
+        Some("use --include=dev instead")),
+    def!("diff", Type::String, true, None,
+        "Package specs or paths to compare with `npm diff`."),
+    def!("diff-dst-prefix", Type::String, Some("b/"),
+        "The prefix `npm diff` gives destination paths."),
+    def!("diff-ignore-all-space", Type::Boolean, Some("false"),
+        "Ignore whitespace when comparing lines in `npm diff`."),
+    def!("diff-name-only", Type::Boolean, Some("false"),
+        "Only print the names of files `npm diff` finds changed."),
+    def!("diff-no-prefix", Type::Boolean, Some("false"),
+        "Leave out the source and destination prefixes in `npm diff`."),
+    def!("diff-src-prefix", Type::String, Some("a/"),
+        "The prefix `npm diff` gives source paths."),
+    def!("diff-text", Type::Boolean, Some("false"),
+        "Treat every file as text in `npm diff`."),
+    def!("diff-unified", Type::Number, Some("3"),
+        "How many lines of context `npm diff` prints."),
+    def!("dry-run", Type::Boolean, Some("false"),
+        "Report what would change without changing anything."),
+    def!("editor", Type::String, None,
+        "The editor `npm edit` and `npm config edit` open."),
+    def!("email", Type::String, None,
+        "The email of the logged-in user."),
+    def!("engine-strict", Type::Boolean, Some("false"),
+        "Refuse to install packages that don't support the current Node.js version."),
+    def!("expect-result-count", Type::Number, None,
+        "Fail unless `npm query` and friends return exactly this many results."),
+    def!("expect-results", Type::Boolean, None,
+        "Fail unless `npm query` and friends return any results."),
+    def!("fetch-retries", Type::Number, Some("2"),
+        "How many times to retry a failed registry request."),
+    def!("fetch-retry-factor", Type::Number, Some("10"),
+        "The exponential backoff factor between retries."),
+    def!("fetch-retry-maxtimeout", Type::Number, Some("60000"),
+        "The longest to wait between retries, in milliseconds."),
+    def!("fetch-retry-mintimeout", Type::Number, Some("10000"),
+        "The shortest to wait between retries, in milliseconds."),
+    def!("fetch-timeout", Type::Number, Some("300000"),
+        "How long to wait for a registry response, in milliseconds."),
+    def!("force", Type::Boolean, Some("false"),
+        "Skip safety checks, doing what npm would otherwise refuse to."),
+    def!("foreground-scripts", Type::Boolean, Some("false"),
+        "Run install scripts in the foreground, sharing the terminal."),
+    def!("format-package-lock", Type::Boolean, Some("true"),
+        "Pretty-print package-lock.json."),
+    def!("fund", Type::Boolean, Some("true"),
+        "Show funding notices after installing."),
+    def!("git", Type::String, Some("git"),
+        "The git executable to use."),
+    def!("git-tag-version", Type::Boolean, Some("true"),
+        "Tag the commit `npm version` makes."),
+    def!("global", Type::Boolean, Some("false"),
+        "Operate on global packages."),
+    def!("global-style", Type::Boolean, false, Some("false"),
+        "Install only direct dependencies at the top of node_modules.",
+        Some("use --install-strategy=shallow instead")),
+    def!("globalconfig", Type::Path, None,
+        "The global config file, `$PREFIX/etc/npmrc` by default."),
+    def!("heading", Type::String, Some("npm"),
+        "The string every debug log line starts with."),
+    def!("https-proxy", Type::Url, None,
+        "A proxy for outgoing HTTPS requests."),
+    def!("if-present", Type::Boolean, Some("false"),
+        "Don't fail `npm run` for scripts that don't exist."),
+    def!("ignore-scripts", Type::Boolean, Some("false"),
+        "Don't run scripts from package.json files."),
+    def!("include", Type::Enum(&["prod", "dev", "optional", "peer"]), true, None,
+        "Dependency types to install, overriding `omit`."),
+    def!("include-staged", Type::Boolean, Some("false"),
+        "Allow installing staged packages."),
+    def!("include-workspace-root", Type::Boolean, Some("false"),
+        "Include the workspace root when workspaces are selected."),
+    def!("init-author-email", Type::String, Some(""),
+        "The author email `npm init` uses."),
+    def!("init-author-name", Type::String, Some(""),
+        "The author name `npm init` uses."),
+    def!("init-author-url", Type::String, Some(""),
+        "The author URL `npm init` uses."),
+    def!("init-license", Type::String, Some("ISC"),
+        "The license `npm init` uses."),
+    def!("init-module", Type::Path, Some("~/.npm-init.js"),
+        "A module loaded by `npm init` to customize it."),
+    def!("init-version", Type::String, Some("1.0.0"),
+        "The version `npm init` uses."),
+    def!("init.author.email", Type::String, false, None,
+        "Alias for `init-author-email`.",
+        Some("use init-author-email instead")),
+    def!("init.author.name", Type::String, false, None,
+        "Alias for `init-author-name`.",
+        Some("use init-author-name instead")),
+    def!("init.author.url", Type::String, false, None,
+        "Alias for `init-author-url`.",
+        Some("use init-author-url instead")),
+    def!("init.license", Type::String, false, None,
+        "Alias for `init-license`.",
+        Some("use init-license instead")),
+    def!("init.module", Type::Path, false, None,
+        "Alias for `init-module`.",
+        Some("use init-module instead")),
+    def!("init.version", Type::String, false, None,
+        "Alias for `init-version`.",
+        Some("use init-version instead")),
+    def!("install-links", Type::Boolean, Some("false"),
+        "Pack and install `file:` dependencies instead of linking them."),
+    def!("install-strategy", Type::Enum(&["hoisted", "nested", "shallow", "linked"]),
+        Some("hoisted"),
+        "How packages are laid out in node_modules."),
+    def!("json", Type::Boolean, Some("false"),
+        "Print output as JSON."),
+    def!("key", Type::String, false, None,
+        "A client key for TLS connections.",
+        Some("use //registry.example.com/:keyfile instead")),
+    def!("legacy-bundling", Type::Boolean, false, Some("false"),
+        "Install packages without deduplicating.",
+        Some("use --install-strategy=nested instead")),
+    def!("legacy-peer-deps", Type::Boolean, Some("false"),
+        "Ignore peer dependencies, like npm 3 through 6."),
+    def!("libc", Type::String, None,
+        "Override the libc used to select optional dependencies."),
+    def!("link", Type::Boolean, Some("false"),
+        "Link global installs into the local project."),
+    def!("local-address", Type::String, None,
+        "The local IP address to connect to the registry from."),
+    def!("location", Type::Enum(&["global", "user", "project"]), Some("user"),
+        "Which config file `npm config` edits."),
+    def!("lockfile-version", Type::Enum(&["1", "2", "3"]), None,
+        "The package-lock.json format version to write."),
+    def!("loglevel", Type::Enum(LOG_LEVELS), Some("notice"),
+        "What level of logs to report."),
+    def!("logs-dir", Type::Path, None,
+        "Where log files are written, `_logs` in the cache by default."),
+    def!("logs-max", Type::Number, Some("10"),
+        "How many log files to keep."),
+    def!("long", Type::Boolean, Some("false"),
+        "Show extended information."),
+    def!("maxsockets", Type::Number, Some("15"),
+        "The most connections to keep open per origin."),
+    def!("message", Type::String, Some("%s"),
+        "The commit message `npm version` uses."),
+    def!("node-gyp", Type::Path, None,
+        "The node-gyp binary used to build native addons."),
+    def!("node-options", Type::String, None,
+        "Options passed to Node.js through NODE_OPTIONS."),
+    def!("node-version", Type::String, None,
+        "The Node.js version packages' `engines` are checked against."),
+    def!("noproxy", Type::String, true, Some(""),
+        "Domains that bypass the proxy, comma-separated."),
+    def!("npm-version", Type::String, None,
+        "The npm version packages' `engines` are checked against."),
+    def!("offline", Type::Boolean, Some("false"),
+        "Never hit the network, use only the cache."),
+    def!("omit", Type::Enum(DEP_TYPES), true, None,
+        "Dependency types to leave out of node_modules."),
+    def!("omit-lockfile-registry-resolved", Type::Boolean, Some("false"),
+        "Leave `resolved` out of registry entries in package-lock.json."),
+    def!("only", Type::Enum(&["prod", "production"]), false, None,
+        "Install only production dependencies.",
+        Some("use --omit=dev instead")),
+    def!("optional", Type::Boolean, false, None,
+        "Install optional dependencies.",
+        Some("use --omit=optional or --include=optional instead")),
+    def!("os", Type::String, None,
+        "Override the OS used to select optional dependencies."),
+    def!("otp", Type::String, None,
+        "A one-time password for two-factor authentication."),
+    def!("pack-destination", Type::String, Some("."),
+        "Where `npm pack` writes tarballs."),
+    def!("package", Type::String, true, None,
+        "The packages `npm exec` installs."),
+    def!("package-lock", Type::Boolean, Some("true"),
+        "Read and write package-lock.json."),
+    def!("package-lock-only", Type::Boolean, Some("false"),
+        "Only update package-lock.json, not node_modules."),
+    def!("parseable", Type::Boolean, Some("false"),
+        "Output parseable text."),
+    def!("prefer-dedupe", Type::Boolean, Some("false"),
+        "Prefer deduplicating over installing the newest version."),
+    def!("prefer-offline", Type::Boolean, Some("false"),
+        "Use cached data without revalidating it."),
+    def!("prefer-online", Type::Boolean, Some("false"),
+        "Always revalidate cached data."),
+    def!("prefix", Type::Path, None,
+        "Where global packages are installed, derived from the Node.js location by default."),
+    def!("preid", Type::String, Some(""),
+        "The prerelease identifier `npm version` uses."),
+    def!("production", Type::Boolean, false, None,
+        "Install only production dependencies.",
+        Some("use --omit=dev instead")),
+    def!("progress", Type::Boolean, Some("true"),
+        "Show a progress bar."),
+    def!("provenance", Type::Boolean, Some("false"),
+        "Publish with a provenance statement from CI."),
+    def!("provenance-file", Type::Path, None,
+        "A provenance bundle to publish instead of generating one."),
+    def!("proxy", Type::Url, None,
+        "A proxy for outgoing HTTP requests."),
+    def!("read-only", Type::Boolean, Some("false"),
+        "Create read-only tokens with `npm token create`."),
+    def!("rebuild-bundle", Type::Boolean, Some("true"),
+        "Rebuild bundled dependencies after installing."),
+    def!("registry", Type::Url, Some("https://registry.npmjs.org/"),
+        "The base URL of the npm registry."),
+    def!("replace-registry-host", Type::String, Some("npmjs"),
+        "Which registry host in package-lock.json to replace with `registry`."),
+    def!("save", Type::Boolean, Some("true"),
+        "Save installed packages to package.json."),
+    def!("save-bundle", Type::Boolean, Some("false"),
+        "Save installed packages as bundled dependencies."),
+    def!("save-dev", Type::Boolean, Some("false"),
+        "Save installed packages as dev dependencies."),
+    def!("save-exact", Type::Boolean, Some("false"),
+        "Save exact versions instead of ranges."),
+    def!("save-optional", Type::Boolean, Some("false"),
+        "Save installed packages as optional dependencies."),
+    def!("save-peer", Type::Boolean, Some("false"),
+        "Save installed packages as peer dependencies."),
+    def!("save-prefix", Type::String, Some("^"),
+        "The range operator saved versions are prefixed with."),
+    def!("save-prod", Type::Boolean, Some("false"),
+        "Save installed packages as regular dependencies."),
+    def!("sbom-format", Type::Enum(&["cyclonedx", "spdx"]), None,
+        "The SBOM format `npm sbom` writes."),
+    def!("sbom-type", Type::Enum(&["library", "application", "framework"]), Some("library"),
+        "The type of package `npm sbom` describes."),
+    def!("scope", Type::String, Some(""),
+        "The scope `npm init` and `npm login` use."),
+    def!("script-shell", Type::String, None,
+        "The shell `npm run` uses, `sh` or `cmd.exe` by default."),
+    def!("searchexclude", Type::String, Some(""),
+        "Exclude search results matching this."),
+    def!("searchlimit", Type::Number, Some("20"),
+        "The most results `npm search` shows."),
+    def!("searchopts", Type::String, Some(""),
+        "Options always passed to `npm search`."),
+    def!("searchstaleness", Type::Number, Some("900"),
+        "How old search results may be, in seconds."),
+    def!("shell", Type::String, None,
+        "The shell `npm exec` uses, $SHELL by default."),
+    def!("shrinkwrap", Type::Boolean, false, Some("true"),
+        "Read npm-shrinkwrap.json.",
+        Some("use --package-lock instead")),
+    def!("sign-git-commit", Type::Boolean, Some("false"),
+        "GPG-sign the commit `npm version` makes."),
+    def!("sign-git-tag", Type::Boolean, Some("false"),
+        "GPG-sign the tag `npm version` makes."),
+    def!("sso-poll-frequency", Type::Number, false, None,
+        "How often to poll during single sign-on.",
+        Some("npm no longer supports SSO")),
+    def!("sso-type", Type::String, false, None,
+        "The single sign-on flow to use.",
+        Some("npm no longer supports SSO")),
+    def!("strict-peer-deps", Type::Boolean, Some("false"),
+        "Fail on conflicting peer dependencies."),
+    def!("strict-ssl", Type::Boolean, Some("true"),
+        "Validate registry TLS certificates."),
+    def!("tag", Type::String, Some("latest"),
+        "The dist-tag installs and publishes use."),
+    def!("tag-version-prefix", Type::String, Some("v"),
+        "The prefix of tags `npm version` makes."),
+    def!("timing", Type::Boolean, Some("false"),
+        "Write timing information to the cache."),
+    def!("tmp", Type::Path, false, None,
+        "Where temporary files are written.",
+        Some("npm uses the OS temporary directory")),
+    def!("umask", Type::Umask, Some("0"),
+        "The file mode mask for new files and directories."),
+    def!("unicode", Type::Boolean, Some("true"),
+        "Use unicode characters in output."),
+    def!("update-notifier", Type::Boolean, Some("true"),
+        "Check for newer npm versions."),
+    def!("usage", Type::Boolean, Some("false"),
+        "Show short usage output."),
+    def!("user-agent", Type::String,
+        Some("npm/{npm-version} node/{node-version} {platform} {arch} workspaces/{workspaces} {ci}"),
+        "The user agent sent with requests."),
+    def!("userconfig", Type::Path, Some("~/.npmrc"),
+        "The user config file."),
+    def!("version", Type::Boolean, Some("false"),
+        "Print npm's version."),
+    def!("versions", Type::Boolean, Some("false"),
+        "Print the versions of npm and its dependencies."),
+    def!("viewer", Type::String, Some("man"),
+        "The program `npm help` opens."),
+    def!("which", Type::Number, None,
+        "Which funding URL `npm fund` opens."),
+    def!("workspace", Type::String, true, None,
+        "Workspaces to run a command in."),
+    def!("workspaces", Type::Boolean, None,
+        "Run a command in all workspaces."),
+    def!("workspaces-update", Type::Boolean, Some("true"),
+        "Update workspace links after installing."),
+    def!("yes", Type::Boolean, None,
+        "Answer yes to any prompt, like the one `npm exec` shows before installing."),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn definitions_are_sorted() {
+        for pair in DEFINITIONS.windows(2) {
+            assert!(
+                pair[0].key < pair[1].key,
+                "{} >= {}",
+                pair[0].key,
+                pair[1].key
+            );
+        }
+    }
+
+    #[test]
+    fn finds_every_definition() {
+        for definition in DEFINITIONS {
+            assert_eq!(super::definition(definition.key), Some(definition));
+        }
+        assert_eq!(super::definition("email").unwrap().ty, Type::String);
+        assert_eq!(super::definition("node-gyp").unwrap().ty, Type::Path);
+        assert_eq!(super::definition("no-such-key"), None);
+    }
+}
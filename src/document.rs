@@ -0,0 +1,476 @@
+//! Lossless editing of `.npmrc` files.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use ini::{self, Line, LineEntry};
+use scope::{normalize, registry_key_scope};
+use secret::redact_line;
+use {Error, Scope};
+
+/// An `.npmrc` file that can be edited without losing comments, ordering or
+/// formatting.
+///
+/// Parsing and displaying a document reproduces the original text byte for
+/// byte. Edits only touch the lines that hold the keys being changed, and new
+/// keys are added after the last top-level entry. `Debug` output masks
+/// credentials, but `Display` doesn't, since it's what gets saved.
+///
+/// ```
+/// let mut doc = npmrc::Document::parse("# mine\nalways-auth=true\nsave = true ; why\n");
+/// doc.set("save", "false");
+/// doc.set_scope_registry("@myorg", "https://npm.pkg.github.com/");
+/// doc.delete("always-auth");
+/// assert_eq!(
+///     doc.to_string(),
+///     "# mine\nsave = false ; why\n@myorg:registry=https://npm.pkg.github.com/\n",
+/// );
+/// ```
+#[derive(Clone, PartialEq, Eq)]
+pub struct Document {
+    // Lines without their `\n`, so `\r` from CRLF files is kept.
+    lines: Vec<String>,
+}
+
+impl Document {
+    /// Parse the contents of an `.npmrc` file.
+    pub fn parse(input: &str) -> Self {
+        Document {
+            lines: input.split('\n').map(str::to_string).collect(),
+        }
+    }
+
+    /// Read an `.npmrc` file, treating a missing file as an empty document.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref();
+        match fs::read_to_string(path) {
+            Ok(contents) => Ok(Document::parse(&contents)),
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => Ok(Document::default()),
+            Err(err) => Err(Error::io(path, err)),
+        }
+    }
+
+    /// Write the document to `path`.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let path = path.as_ref();
+        fs::write(path, self.to_string()).map_err(|err| Error::io(path, err))
+    }
+
+    /// The value of `key`, as npm would read it.
+    ///
+    /// For a list written with `key[]=`, this is its last item.
+    pub fn get(&self, key: &str) -> Option<String> {
+        self.entries_for(key).last().map(|(_, entry)| entry.value)
+    }
+
+    /// Every value of `key`, as npm would read a list.
+    ///
+    /// Each `key[]=value` line adds an item, while a `key=value` line starts
+    /// the list over.
+    pub fn get_all(&self, key: &str) -> Vec<String> {
+        let mut values = Vec::new();
+        for (_, entry) in self.entries_for(key) {
+            if !is_append(&entry) {
+                values.clear();
+            }
+            values.push(entry.value);
+        }
+        values
+    }
+
+    /// All keys in the document, in order of first appearance.
+    ///
+    /// List keys are returned without their `[]`.
+    pub fn keys(&self) -> Vec<String> {
+        let mut keys: Vec<String> = Vec::new();
+        for (_, entry) in self.entries() {
+            let key = entry.key.strip_suffix("[]").unwrap_or(&entry.key);
+            if !keys.iter().any(|existing| existing == key) {
+                keys.push(key.to_string());
+            }
+        }
+        keys
+    }
+
+    /// Set `key` to `value`.
+    ///
+    /// If the key already exists, only its value is rewritten, keeping any
+    /// spacing and inline comment on the line. Otherwise a `key=value` line is
+    /// added. A list written with `key[]=` is replaced by the single value.
+    pub fn set(&mut self, key: &str, value: &str) {
+        let items: Vec<usize> = self
+            .entries_for(key)
+            .filter(|(_, entry)| is_append(entry))
+            .map(|(index, _)| index)
+            .collect();
+        self.remove_lines(&items);
+
+        let existing = self.entries_for(key).last();
+
+        match existing {
+            Some((index, entry)) => {
+                let value = ini::quote(value);
+                let line = &mut self.lines[index];
+                match entry.value_span {
+                    Some(span) => line.replace_range(span, &value),
+                    None => line.insert_str(entry.key_span.end, &format!("={}", value)),
+                }
+            }
+            None => {
+                let line = format!("{}={}", ini::quote(key), ini::quote(value));
+                match items.first() {
+                    Some(&index) => self.insert_at(index, line),
+                    None => self.insert(line),
+                }
+            }
+        }
+    }
+
+    /// Replace every assignment of `key` with a `key[]=value` line for each
+    /// of `values`.
+    ///
+    /// The list is written where the key was first assigned, or added after
+    /// the last top-level entry.
+    pub fn set_all<S: AsRef<str>>(&mut self, key: &str, values: &[S]) {
+        let indices: Vec<usize> = self.entries_for(key).map(|(index, _)| index).collect();
+        self.remove_lines(&indices);
+
+        let lines = values.iter().map(|value| list_line(key, value.as_ref()));
+        match indices.first() {
+            Some(&first) => {
+                for (offset, line) in lines.enumerate() {
+                    self.insert_at(first + offset, line);
+                }
+            }
+            None => lines.for_each(|line| self.insert(line)),
+        }
+    }
+
+    /// Add `value` to the list `key`, as a `key[]=value` line after its last
+    /// item.
+    pub fn push(&mut self, key: &str, value: &str) {
+        let line = list_line(key, value);
+        match self.entries_for(key).last() {
+            Some((index, _)) => self.insert_at(index + 1, line),
+            None => self.insert(line),
+        }
+    }
+
+    /// Remove every assignment of `key`, returning whether any was found.
+    pub fn delete(&mut self, key: &str) -> bool {
+        let indices: Vec<usize> = self.entries_for(key).map(|(index, _)| index).collect();
+        self.remove_lines(&indices);
+        !indices.is_empty()
+    }
+
+    /// Rename every assignment of `from` to `to`, keeping their values.
+    ///
+    /// Returns whether `from` was found.
+    pub fn rename(&mut self, from: &str, to: &str) -> bool {
+        let spans: Vec<_> = self
+            .entries_for(from)
+            .map(|(index, entry)| (index, is_append(&entry), entry.key_span))
+            .collect();
+
+        for &(index, append, ref span) in &spans {
+            let key = if append {
+                format!("{}[]", ini::quote(to))
+            } else {
+                ini::quote(to)
+            };
+            self.lines[index].replace_range(span.clone(), &key);
+        }
+        !spans.is_empty()
+    }
+
+    /// Every scope mapped to a registry with `@scope:registry`, sorted by
+    /// name.
+    pub fn scopes(&self) -> Vec<Scope> {
+        let mut scopes: Vec<Scope> = Vec::new();
+        for key in self.keys() {
+            if let (Some(name), Some(registry)) = (registry_key_scope(&key), self.get(&key)) {
+                scopes.retain(|scope| scope.name != name);
+                scopes.push(Scope::new(&name, &registry));
+            }
+        }
+        scopes.sort_by(|a, b| a.name.cmp(&b.name));
+        scopes
+    }
+
+    /// Point packages in `scope` at `registry`, by setting `@scope:registry`.
+    ///
+    /// `scope` may be given with or without its `@`. An existing mapping is
+    /// updated in place, even if its key is cased differently.
+    pub fn set_scope_registry(&mut self, scope: &str, registry: &str) {
+        let name = normalize(scope);
+        let existing: Vec<String> = self
+            .keys()
+            .into_iter()
+            .filter(|key| registry_key_scope(key).as_ref() == Some(&name))
+            .collect();
+
+        match existing.split_last() {
+            Some((last, rest)) => {
+                for key in rest {
+                    self.delete(key);
+                }
+                self.set(last, registry);
+            }
+            None => self.set(&Scope::new(&name, registry).key(), registry),
+        }
+    }
+
+    /// Remove the registry mapping for `scope`, returning whether there was
+    /// one.
+    pub fn remove_scope_registry(&mut self, scope: &str) -> bool {
+        let name = normalize(scope);
+        let keys: Vec<String> = self
+            .keys()
+            .into_iter()
+            .filter(|key| registry_key_scope(key).as_ref() == Some(&name))
+            .collect();
+
+        let mut removed = false;
+        for key in keys {
+            removed |= self.delete(&key);
+        }
+        removed
+    }
+
+    // Top-level entries with the index of the line they're on.
+    fn entries(&self) -> impl Iterator<Item = (usize, LineEntry)> + '_ {
+        let mut in_section = false;
+        self.lines
+            .iter()
+            .enumerate()
+            .filter_map(move |(index, line)| {
+                let line = line.strip_suffix('\r').unwrap_or(line);
+                match ini::parse_line(line) {
+                    Line::Section => in_section = true,
+                    Line::Entry(entry) if !in_section => return Some((index, entry)),
+                    _ => {}
+                }
+                None
+            })
+    }
+
+    // Top-level assignments of `key`, written either as `key` or `key[]`.
+    fn entries_for<'a>(&'a self, key: &'a str) -> impl Iterator<Item = (usize, LineEntry)> + 'a {
+        self.entries()
+            .filter(move |(_, entry)| entry.key == key || entry.key.strip_suffix("[]") == Some(key))
+    }
+
+    fn remove_lines(&mut self, indices: &[usize]) {
+        for &index in indices.iter().rev() {
+            self.lines.remove(index);
+        }
+    }
+
+    // Add a line after the last top-level entry, or before the first section.
+    fn insert(&mut self, line: String) {
+        // Stay in front of the final newline, if the file has one.
+        let end = match self.lines.last() {
+            Some(last) if last.is_empty() => self.lines.len() - 1,
+            _ => self.lines.len(),
+        };
+        let first_section = self
+            .lines
+            .iter()
+            .position(|line| matches!(ini::parse_line(line), Line::Section));
+        let index = match self.entries().last() {
+            Some((index, _)) => index + 1,
+            None => first_section.unwrap_or(end),
+        };
+
+        self.insert_at(index, line);
+    }
+
+    // Add a line at `index`, matching the file's line endings.
+    fn insert_at(&mut self, index: usize, mut line: String) {
+        if self.lines.iter().any(|line| line.ends_with('\r')) {
+            line.push('\r');
+        }
+        self.lines.insert(index, line);
+    }
+}
+
+// Whether an entry adds to a list, rather than setting the key outright.
+fn is_append(entry: &LineEntry) -> bool {
+    entry.key.ends_with("[]")
+}
+
+fn list_line(key: &str, value: &str) -> String {
+    format!("{}[]={}", ini::quote(key), ini::quote(value))
+}
+
+impl Default for Document {
+    fn default() -> Self {
+        Document::parse("")
+    }
+}
+
+impl fmt::Debug for Document {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let lines: Vec<_> = self.lines.iter().map(|line| redact_line(line)).collect();
+        f.debug_struct("Document").field("lines", &lines).finish()
+    }
+}
+
+impl fmt::Display for Document {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.lines.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edit<F: FnOnce(&mut Document)>(input: &str, f: F) -> String {
+        let mut document = Document::parse(input);
+        f(&mut document);
+        document.to_string()
+    }
+
+    #[test]
+    fn round_trips_byte_for_byte() {
+        let inputs = [
+            "",
+            "\n",
+            "registry=https://registry.npmjs.org/",
+            "registry=https://registry.npmjs.org/\n",
+            "; comment\n# another\n\n  key = value  ; inline\n",
+            "a=1\r\nb=2\r\n",
+            "mixed=1\r\nendings=2\n",
+            "\"quoted key\"=\"quoted value\"\n'single'='quotes'\n",
+            "ca[]=one\nca[]=two\n",
+            "top=1\n[section]\nnested=2\n",
+            "\u{feff}bom=1\n",
+            "not an entry\n=\n[\n",
+            "\t tabs\t=\tvalue\t\n\n\n",
+        ];
+        for input in &inputs {
+            assert_eq!(Document::parse(input).to_string(), *input);
+        }
+    }
+
+    #[test]
+    fn set_rewrites_only_the_value() {
+        assert_eq!(
+            edit(
+                "registry = https://a.example/  ; main\nsave=true\n",
+                |doc| { doc.set("registry", "https://b.example/") }
+            ),
+            "registry = https://b.example/  ; main\nsave=true\n"
+        );
+        assert_eq!(
+            edit("# note\nsave\n", |doc| doc.set("save", "false")),
+            "# note\nsave=false\n"
+        );
+    }
+
+    #[test]
+    fn set_adds_missing_keys_after_the_last_entry() {
+        assert_eq!(
+            edit("a=1\n\n# trailing comment\n", |doc| doc.set("b", "2")),
+            "a=1\nb=2\n\n# trailing comment\n"
+        );
+        assert_eq!(edit("", |doc| doc.set("a", "1")), "a=1\n");
+        assert_eq!(edit("\n", |doc| doc.set("a", "1")), "\na=1\n");
+        assert_eq!(edit("a=1", |doc| doc.set("b", "2")), "a=1\nb=2");
+    }
+
+    #[test]
+    fn set_inserts_before_sections() {
+        assert_eq!(
+            edit("a=1\n[section]\nb=2\n", |doc| doc.set("c", "3")),
+            "a=1\nc=3\n[section]\nb=2\n"
+        );
+        assert_eq!(
+            edit("; header\n[section]\nb=2\n", |doc| doc.set("c", "3")),
+            "; header\nc=3\n[section]\nb=2\n"
+        );
+        // Keys in a section aren't top-level, so they're left alone.
+        assert_eq!(
+            edit("[section]\nb=2\n", |doc| doc.set("b", "3")),
+            "b=3\n[section]\nb=2\n"
+        );
+    }
+
+    #[test]
+    fn keeps_crlf_line_endings() {
+        assert_eq!(
+            edit("a=1\r\nb=2\r\n", |doc| doc.set("c", "3")),
+            "a=1\r\nb=2\r\nc=3\r\n"
+        );
+        assert_eq!(
+            edit("a=1\r\nb=2\r\n", |doc| doc.set("a", "9")),
+            "a=9\r\nb=2\r\n"
+        );
+        assert_eq!(
+            edit("ca[]=x\r\n", |doc| doc.push("ca", "y")),
+            "ca[]=x\r\nca[]=y\r\n"
+        );
+        assert_eq!(Document::parse("a=1\r\n").get("a").as_deref(), Some("1"));
+    }
+
+    #[test]
+    fn set_collapses_lists() {
+        assert_eq!(
+            edit("ca[]=x\nz=1\nca[]=y\n", |doc| doc.set("ca", "v")),
+            "ca=v\nz=1\n"
+        );
+        assert_eq!(edit("ca=x\nca[]=y\n", |doc| doc.set("ca", "v")), "ca=v\n");
+    }
+
+    #[test]
+    fn lists() {
+        let doc = Document::parse("ca[]=a\nca[]=b\nca=c\nca[]=d\n");
+        assert_eq!(doc.get_all("ca"), ["c", "d"]);
+        assert_eq!(doc.get("ca").as_deref(), Some("d"));
+        assert_eq!(doc.keys(), ["ca"]);
+
+        assert_eq!(
+            edit("ca[]=a\nz=1\n", |doc| doc.push("ca", "b")),
+            "ca[]=a\nca[]=b\nz=1\n"
+        );
+        assert_eq!(
+            edit("z=1\nca=a\nca[]=b\n", |doc| doc.set_all("ca", &["x", "y"])),
+            "z=1\nca[]=x\nca[]=y\n"
+        );
+    }
+
+    #[test]
+    fn delete_and_rename() {
+        let mut doc = Document::parse("# keep\na=1\nb=2\na[]=3\n");
+        assert!(doc.delete("a"));
+        assert!(!doc.delete("a"));
+        assert_eq!(doc.to_string(), "# keep\nb=2\n");
+
+        assert_eq!(
+            edit("strict_ssl = false ; typo\nx[]=1\n", |doc| {
+                doc.rename("strict_ssl", "strict-ssl");
+                doc.rename("x", "y");
+            }),
+            "strict-ssl = false ; typo\ny[]=1\n"
+        );
+    }
+
+    #[test]
+    fn scope_registries() {
+        let mut doc = Document::parse("@Org:registry=https://old.example/\n");
+        doc.set_scope_registry("org", "https://new.example/");
+        assert_eq!(doc.to_string(), "@Org:registry=https://new.example/\n");
+        assert!(doc.remove_scope_registry("@org"));
+        assert_eq!(doc.to_string(), "");
+    }
+
+    #[test]
+    fn debug_masks_credentials() {
+        let doc = Document::parse("//r.example/:_authToken=npm_secret\n");
+        assert!(!format!("{:?}", doc).contains("npm_secret"));
+        assert!(doc.to_string().contains("npm_secret"));
+    }
+}
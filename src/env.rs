@@ -0,0 +1,227 @@
+//! Environment variable handling.
+
+use std::collections::{BTreeMap, HashMap};
+use std::error;
+use std::fmt;
+
+use loader::Setting;
+use {Layer, Source};
+
+const PREFIX: &str = "npm_config_";
+
+/// A `${VAR}` reference to an environment variable that isn't set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnresolvedEnv {
+    /// The name of the missing variable.
+    pub name: String,
+
+    /// The key whose key or value referenced the variable.
+    pub key: String,
+
+    /// Where the reference was found.
+    pub source: Source,
+}
+
+impl fmt::Display for UnresolvedEnv {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "environment variable `{}` used by `{}` is not set ({})",
+            self.name, self.key, self.source
+        )
+    }
+}
+
+impl error::Error for UnresolvedEnv {}
+
+/// Collect the `npm_config_*` layer from a set of environment variables.
+///
+/// The prefix is matched case-insensitively and empty values are ignored.
+/// Keys are lowercased and `_` becomes `-`, except for a leading `_` and for
+/// nerf-darted `//host/:key` keys, which are kept as written. When the same
+/// key is set with both a lowercase and a differently cased prefix, the
+/// lowercase `npm_config_` variable wins.
+///
+/// ```
+/// let config = npmrc::env_config(vec![("NPM_CONFIG_STRICT_SSL", "false")]);
+/// assert_eq!(config["strict-ssl"], "false");
+/// ```
+pub fn env_config<I, K, V>(vars: I) -> BTreeMap<String, String>
+where
+    I: IntoIterator<Item = (K, V)>,
+    K: AsRef<str>,
+    V: Into<String>,
+{
+    let mut vars: Vec<(String, String)> = vars
+        .into_iter()
+        .map(|(name, value)| (name.as_ref().to_string(), value.into()))
+        .collect();
+    // Process lowercase prefixes last so they override any other spelling,
+    // and sort the rest so the outcome doesn't depend on iteration order.
+    vars.sort_by(|a, b| (a.0.starts_with(PREFIX), &a.0).cmp(&(b.0.starts_with(PREFIX), &b.0)));
+
+    let mut config = BTreeMap::new();
+    for (name, value) in vars {
+        if name.len() <= PREFIX.len() || value.is_empty() {
+            continue;
+        }
+        if !name.is_char_boundary(PREFIX.len())
+            || !name[..PREFIX.len()].eq_ignore_ascii_case(PREFIX)
+        {
+            continue;
+        }
+
+        let key = &name[PREFIX.len()..];
+        let key = if key.starts_with("//") {
+            key.to_string()
+        } else {
+            normalize_key(key)
+        };
+        config.insert(key, value);
+    }
+
+    config
+}
+
+/// The `npm_config_*` layer of `vars` as settings, and the references to
+/// unset variables in it. With `expand`, `${VAR}` references are expanded
+/// against `vars` themselves.
+pub(crate) fn env_layer(
+    vars: &HashMap<String, String>,
+    expand: bool,
+) -> (Vec<Setting>, Vec<UnresolvedEnv>) {
+    let mut settings: Vec<Setting> = env_config(vars)
+        .into_iter()
+        .map(|(key, value)| Setting {
+            key,
+            value,
+            source: Source::new(Layer::Env),
+            append: false,
+        })
+        .collect();
+    let unresolved = if expand {
+        expand_settings(&mut settings, |name| vars.get(name).cloned())
+    } else {
+        Vec::new()
+    };
+    (settings, unresolved)
+}
+
+// Lowercase the key and turn `_` into `-`, except for a leading `_`.
+pub(crate) fn normalize_key(key: &str) -> String {
+    key.char_indices()
+        .map(|(i, c)| {
+            if c == '_' && i > 0 {
+                '-'
+            } else {
+                c.to_ascii_lowercase()
+            }
+        })
+        .collect()
+}
+
+/// Expand references in the keys and values of every setting.
+///
+/// Like npm, references to unset variables are kept as written. They're
+/// returned in the order they appear.
+pub(crate) fn expand_settings<F>(settings: &mut [Setting], lookup: F) -> Vec<UnresolvedEnv>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut unresolved = Vec::new();
+    for setting in settings {
+        let (key, mut names) = expand(&setting.key, &lookup);
+        let (value, value_names) = expand(&setting.value, &lookup);
+        names.extend(value_names);
+        unresolved.extend(names.into_iter().map(|name| UnresolvedEnv {
+            name,
+            key: setting.key.clone(),
+            source: setting.source.clone(),
+        }));
+        setting.key = key;
+        setting.value = value;
+    }
+    unresolved
+}
+
+/// Replace `${VAR}` references the way npm does.
+///
+/// `${VAR?}` expands to an empty string when `VAR` is unset, and a reference
+/// preceded by an odd number of backslashes is kept literally. So is a
+/// reference to an unset variable, whose name is returned alongside.
+pub(crate) fn expand<F>(input: &str, lookup: F) -> (String, Vec<String>)
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut out = String::with_capacity(input.len());
+    let mut unresolved = Vec::new();
+    let mut rest = input;
+
+    while let Some(start) = rest.find("${") {
+        let (before, tail) = rest.split_at(start);
+        let reference = match parse_reference(tail) {
+            Some(reference) => reference,
+            None => {
+                out.push_str(before);
+                out.push_str("${");
+                rest = &tail[2..];
+                continue;
+            }
+        };
+
+        let text = before.trim_end_matches('\\');
+        let escapes = before.len() - text.len();
+        out.push_str(text);
+        out.push_str(&"\\".repeat(escapes / 2));
+
+        if escapes % 2 == 1 {
+            out.push_str(&tail[..reference.len]);
+        } else {
+            match lookup(reference.name) {
+                Some(value) => out.push_str(&value),
+                None if reference.optional => {}
+                None => {
+                    out.push_str(&tail[..reference.len]);
+                    unresolved.push(reference.name.to_string());
+                }
+            }
+        }
+
+        rest = &tail[reference.len..];
+    }
+
+    out.push_str(rest);
+    (out, unresolved)
+}
+
+struct Reference<'a> {
+    name: &'a str,
+    optional: bool,
+    len: usize,
+}
+
+// Parse a `${NAME}` or `${NAME?}` reference at the start of `input`.
+fn parse_reference(input: &str) -> Option<Reference<'_>> {
+    let end = input[2..].find(['$', '{', '}', '?'])? + 2;
+    let name = &input[2..end];
+    if name.is_empty() {
+        return None;
+    }
+
+    let tail = &input[end..];
+    if tail.starts_with("?}") {
+        Some(Reference {
+            name,
+            optional: true,
+            len: end + 2,
+        })
+    } else if tail.starts_with('}') {
+        Some(Reference {
+            name,
+            optional: false,
+            len: end + 1,
+        })
+    } else {
+        None
+    }
+}
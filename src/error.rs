@@ -0,0 +1,291 @@
+//! The errors reading and loading config can fail with.
+
+use serde_json;
+use std::error;
+use std::fmt;
+use std::io;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use ini::{self, Line};
+use secret::{redact, redact_line};
+use {InvalidValue, Source, UnresolvedEnv};
+
+/// Everything that can go wrong while reading config.
+///
+/// An error caused by a line of an `.npmrc` file displays as a diagnostic
+/// that points at the offending part of the line:
+///
+/// ```text
+/// invalid value `abc` for `fetch-retries`, expected a number
+///  --> /home/me/.npmrc:3:15
+///   |
+/// 3 | fetch-retries=abc
+///   |               ^^^
+/// ```
+#[derive(Debug)]
+pub enum Error {
+    /// The user's home directory couldn't be determined.
+    HomeDirNotFound,
+
+    /// A config file that has to exist doesn't.
+    NotFound {
+        /// The missing file.
+        path: PathBuf,
+    },
+
+    /// Reading or writing a file failed.
+    Io {
+        /// The file being accessed, if the error concerns one.
+        path: Option<PathBuf>,
+
+        /// The underlying error.
+        error: io::Error,
+    },
+
+    /// A line of a config file is malformed.
+    Syntax {
+        /// The file the line is in, if it was read from a file rather than a
+        /// string.
+        path: Option<PathBuf>,
+
+        /// One-based line number.
+        line: usize,
+
+        /// One-based column of the problem, in characters.
+        column: usize,
+
+        /// What's wrong with the line.
+        message: String,
+
+        /// The text of the line.
+        text: String,
+    },
+
+    /// A value doesn't have the type its key expects.
+    ///
+    /// Only returned when loading with `Loader::strict`; otherwise such values
+    /// are skipped and listed by `Npmrc::invalid_values`.
+    InvalidValue(Box<InvalidValue>),
+
+    /// A `${VAR}` reference names an environment variable that isn't set.
+    ///
+    /// Only returned when loading with `Loader::strict`; otherwise the
+    /// reference is kept as written and listed by `Npmrc::unresolved_env`.
+    UnresolvedEnv(Box<UnresolvedEnv>),
+
+    /// A certificate or private key isn't valid PEM.
+    Pem {
+        /// The key that holds the PEM, or names the file it's in.
+        key: String,
+
+        /// The file the PEM was read from, if it wasn't inline.
+        path: Option<PathBuf>,
+
+        /// What's wrong with it.
+        message: String,
+    },
+
+    /// The input to `Npmrc::from_json` isn't a JSON object of config values.
+    Json(serde_json::Error),
+}
+
+impl Error {
+    // An error accessing `path`, telling a missing file apart.
+    pub(crate) fn io(path: &Path, error: io::Error) -> Self {
+        if error.kind() == io::ErrorKind::NotFound {
+            Error::NotFound {
+                path: path.to_path_buf(),
+            }
+        } else {
+            Error::Io {
+                path: Some(path.to_path_buf()),
+                error,
+            }
+        }
+    }
+
+    pub(crate) fn pem(key: &str, path: Option<&Path>, message: &str) -> Self {
+        Error::Pem {
+            key: key.to_string(),
+            path: path.map(Path::to_path_buf),
+            message: message.to_string(),
+        }
+    }
+
+    pub(crate) fn syntax(path: Option<&Path>, error: ini::SyntaxError) -> Self {
+        let text = redact_line(&error.text).into_owned();
+        // Masking can shorten the line, so keep the column on it.
+        let column = error.text[..error.offset].chars().count() + 1;
+        Error::Syntax {
+            path: path.map(Path::to_path_buf),
+            line: error.line,
+            column: column.min(text.chars().count() + 1),
+            message: error.message.to_string(),
+            text,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::HomeDirNotFound => f.write_str("user's home directory not found"),
+            Error::NotFound { ref path } => write!(f, "{} not found", path.display()),
+            Error::Io {
+                path: Some(ref path),
+                ref error,
+            } => write!(f, "couldn't access {}: {}", path.display(), error),
+            Error::Io {
+                path: None,
+                ref error,
+            } => write!(f, "{}", error),
+            Error::Syntax {
+                ref path,
+                line,
+                column,
+                ref message,
+                ref text,
+            } => {
+                f.write_str(message)?;
+                snippet(f, path.as_deref(), line, text, column, 1)
+            }
+            Error::InvalidValue(ref error) => {
+                write!(
+                    f,
+                    "invalid value `{}` for `{}`, expected {}",
+                    redact(&error.key, &error.value),
+                    error.key,
+                    error.expected
+                )?;
+                located(f, &error.source, value_span)
+            }
+            Error::UnresolvedEnv(ref error) => {
+                write!(
+                    f,
+                    "environment variable `{}` used by `{}` is not set",
+                    error.name, error.key
+                )?;
+                located(f, &error.source, |text| {
+                    let start = text.find(&format!("${{{}", error.name))?;
+                    let end = text[start..]
+                        .find('}')
+                        .map_or(text.len(), |end| start + end + 1);
+                    Some(start..end)
+                })
+            }
+            Error::Pem {
+                ref key,
+                path: Some(ref path),
+                ref message,
+            } => write!(
+                f,
+                "invalid PEM in {} (from `{}`): {}",
+                path.display(),
+                key,
+                message
+            ),
+            Error::Pem {
+                ref key,
+                path: None,
+                ref message,
+            } => write!(f, "invalid PEM in `{}`: {}", key, message),
+            Error::Json(ref error) => write!(f, "invalid config JSON: {}", error),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            Error::Io { ref error, .. } => Some(error),
+            Error::InvalidValue(ref error) => Some(&**error),
+            Error::UnresolvedEnv(ref error) => Some(&**error),
+            Error::Json(ref error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error::Io { path: None, error }
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(error: serde_json::Error) -> Self {
+        Error::Json(error)
+    }
+}
+
+impl From<InvalidValue> for Error {
+    fn from(error: InvalidValue) -> Self {
+        Error::InvalidValue(Box::new(error))
+    }
+}
+
+impl From<UnresolvedEnv> for Error {
+    fn from(error: UnresolvedEnv) -> Self {
+        Error::UnresolvedEnv(Box::new(error))
+    }
+}
+
+// The span of the key on an entry line.
+pub(crate) fn key_span(text: &str) -> Option<Range<usize>> {
+    match ini::parse_line(text) {
+        Line::Entry(entry) => Some(entry.key_span),
+        _ => None,
+    }
+}
+
+// The span of the value on an entry line, or of the key for a bare key.
+pub(crate) fn value_span(text: &str) -> Option<Range<usize>> {
+    match ini::parse_line(text) {
+        Line::Entry(entry) => Some(entry.value_span.unwrap_or(entry.key_span)),
+        _ => None,
+    }
+}
+
+// Point at the part of the source line `find` picks out, or name the layer
+// when the value didn't come from a file.
+pub(crate) fn located<F>(f: &mut fmt::Formatter, source: &Source, find: F) -> fmt::Result
+where
+    F: Fn(&str) -> Option<Range<usize>>,
+{
+    if let (Some(line), Some(text)) = (source.line, &source.text) {
+        if let Some(span) = find(text) {
+            let column = text[..span.start].chars().count() + 1;
+            let width = text[span].chars().count();
+            return snippet(f, source.path.as_deref(), line, text, column, width);
+        }
+    }
+    write!(f, " ({})", source)
+}
+
+// Render the `-->` location, the line and a caret underneath the problem.
+// Config that didn't come from a file is located as `<input>`.
+fn snippet(
+    f: &mut fmt::Formatter,
+    path: Option<&Path>,
+    line: usize,
+    text: &str,
+    column: usize,
+    width: usize,
+) -> fmt::Result {
+    let gutter = " ".repeat(line.to_string().len());
+    // Keep tabs so the caret lines up with the text above it.
+    let indent: String = text
+        .chars()
+        .take(column - 1)
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+
+    match path {
+        Some(path) => write!(f, "\n{}--> {}:{}:{}", gutter, path.display(), line, column)?,
+        None => write!(f, "\n{}--> <input>:{}:{}", gutter, line, column)?,
+    }
+    write!(f, "\n{} |", gutter)?;
+    write!(f, "\n{} | {}", line, text)?;
+    write!(f, "\n{} | {}{}", gutter, indent, "^".repeat(width.max(1)))
+}
@@ -0,0 +1,150 @@
+//! The filesystem config is read from.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// The filesystem operations loading config needs.
+///
+/// `Loader::fs` swaps the real filesystem for another implementation, like
+/// `MemoryFs`, so config can be loaded from fixtures without touching the
+/// disk.
+pub trait FileSystem: fmt::Debug + Send + Sync {
+    /// Read a whole file.
+    fn read_to_string(&self, path: &Path) -> io::Result<String>;
+
+    /// Whether `path` is a file.
+    fn is_file(&self, path: &Path) -> bool;
+
+    /// Whether `path` is a directory.
+    fn is_dir(&self, path: &Path) -> bool;
+
+    /// Resolve symlinks in `path`. Defaults to returning `path` as is.
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        Ok(path.to_path_buf())
+    }
+}
+
+impl<F: FileSystem + ?Sized> FileSystem for Arc<F> {
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        (**self).read_to_string(path)
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        (**self).is_file(path)
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        (**self).is_dir(path)
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        (**self).canonicalize(path)
+    }
+}
+
+/// The real filesystem, through `std::fs`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RealFs;
+
+impl FileSystem for RealFs {
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        fs::read_to_string(path)
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        path.is_file()
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        path.is_dir()
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        fs::canonicalize(path)
+    }
+}
+
+/// A filesystem that only exists in memory.
+///
+/// The parents of every file are directories, and other directories can be
+/// added with `dir`.
+///
+/// ```
+/// # fn main() -> Result<(), npmrc::Error> {
+/// let fs = npmrc::MemoryFs::new()
+///     .file("/home/me/.npmrc", "registry=https://registry.example.com/")
+///     .file("/repo/package.json", "{}")
+///     .dir("/repo/node_modules");
+/// let npmrc = npmrc::Loader::new()
+///     .fs(fs)
+///     .home("/home/me")
+///     .cwd("/repo")
+///     .env(Vec::<(String, String)>::new())
+///     .load()?;
+/// assert_eq!(npmrc.registry, "https://registry.example.com/");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MemoryFs {
+    files: BTreeMap<PathBuf, String>,
+    dirs: BTreeSet<PathBuf>,
+}
+
+impl MemoryFs {
+    /// Create an empty filesystem.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a file, replacing any file already at `path`.
+    pub fn file<P: Into<PathBuf>, C: Into<String>>(mut self, path: P, contents: C) -> Self {
+        self.insert(path, contents);
+        self
+    }
+
+    /// Add a directory.
+    pub fn dir<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.dirs.insert(path.into());
+        self
+    }
+
+    /// Add or replace a file in place.
+    pub fn insert<P: Into<PathBuf>, C: Into<String>>(&mut self, path: P, contents: C) {
+        self.files.insert(path.into(), contents.into());
+    }
+}
+
+impl FileSystem for MemoryFs {
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        match self.files.get(path) {
+            Some(contents) => Ok(contents.clone()),
+            None if self.is_dir(path) => Err(io::Error::other("is a directory")),
+            None => Err(io::ErrorKind::NotFound.into()),
+        }
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        self.files.contains_key(path)
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        self.dirs.iter().any(|dir| dir.starts_with(path))
+            || self
+                .files
+                .keys()
+                .any(|file| file != path && file.starts_with(path))
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        if self.is_file(path) || self.is_dir(path) {
+            Ok(path.to_path_buf())
+        } else {
+            Err(io::ErrorKind::NotFound.into())
+        }
+    }
+}
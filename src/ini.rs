@@ -0,0 +1,268 @@
+//! A line-aware parser for the ini dialect npm reads `.npmrc` files with.
+
+use std::ops::Range;
+
+/// A single `key=value` line.
+#[derive(Debug, Clone)]
+pub(crate) struct Entry {
+    pub key: String,
+    pub value: String,
+    /// One-based line number.
+    pub line: usize,
+    /// The whole line, for diagnostics.
+    pub text: String,
+}
+
+/// A line that can't be read as a blank line, section or entry.
+#[derive(Debug, Clone)]
+pub(crate) struct SyntaxError {
+    /// One-based line number.
+    pub line: usize,
+    /// Byte offset of the problem within the line.
+    pub offset: usize,
+    pub message: &'static str,
+    /// The whole line.
+    pub text: String,
+}
+
+/// What a single line of an ini file holds.
+pub(crate) enum Line {
+    /// A blank line or a comment.
+    Blank,
+
+    /// A `[section]` header.
+    Section,
+
+    /// A `key=value` line, or a bare `key`.
+    Entry(LineEntry),
+
+    /// A malformed line, with the byte offset of the problem.
+    Invalid(usize, &'static str),
+}
+
+/// A parsed `key=value` line, with the positions of its parts.
+pub(crate) struct LineEntry {
+    pub key: String,
+    pub value: String,
+    /// Byte range of the key within the line.
+    pub key_span: Range<usize>,
+    /// Byte range of the value within the line, `None` for a bare key.
+    pub value_span: Option<Range<usize>>,
+}
+
+/// Parse the top-level entries of an ini file.
+///
+/// Like npm, a key without `=` is set to `true`, comments start at an
+/// unescaped `;` or `#`, and quoted values are taken verbatim. Entries inside
+/// `[section]`s are not part of npm's config and are skipped.
+///
+/// Lines npm would silently drop or misread, like `=value` or an unterminated
+/// quote, are reported as syntax errors.
+pub(crate) fn parse(input: &str) -> Result<Vec<Entry>, SyntaxError> {
+    let mut entries = Vec::new();
+    let mut in_section = false;
+
+    for (index, line) in lines(input).enumerate() {
+        match parse_line(line) {
+            Line::Blank => {}
+            Line::Section => in_section = true,
+            Line::Entry(_) if in_section => {}
+            Line::Entry(entry) => entries.push(Entry {
+                key: entry.key,
+                value: entry.value,
+                line: index + 1,
+                text: line.to_string(),
+            }),
+            Line::Invalid(offset, message) => {
+                return Err(SyntaxError {
+                    line: index + 1,
+                    offset,
+                    message,
+                    text: line.to_string(),
+                })
+            }
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Parse a single line, without its line terminator.
+pub(crate) fn parse_line(line: &str) -> Line {
+    let trimmed = line.trim();
+    if trimmed.is_empty() || trimmed.starts_with(';') || trimmed.starts_with('#') {
+        return Line::Blank;
+    }
+
+    let indent = line.len() - line.trim_start().len();
+    if trimmed.starts_with('[') {
+        if trimmed.ends_with(']') {
+            return Line::Section;
+        }
+        return Line::Invalid(indent, "unterminated section header");
+    }
+
+    let (key_span, value_span) = match line.find('=') {
+        Some(eq) => (
+            trim_span(line, 0..eq),
+            Some(value_span(line, eq + 1..line.len())),
+        ),
+        None => (trim_span(line, 0..line.len()), None),
+    };
+
+    for span in Some(&key_span).into_iter().chain(value_span.as_ref()) {
+        let text = &line[span.clone()];
+        if (text.starts_with('"') || text.starts_with('\'')) && !is_quoted(text) {
+            return Line::Invalid(span.start, "unterminated quoted string");
+        }
+    }
+
+    let key = unquote(&line[key_span.clone()]);
+    if key.is_empty() {
+        return Line::Invalid(indent, "missing key before `=`");
+    }
+
+    let value = match value_span {
+        Some(ref span) => unquote(&line[span.clone()]),
+        None => "true".to_string(),
+    };
+
+    Line::Entry(LineEntry {
+        key,
+        value,
+        key_span,
+        value_span,
+    })
+}
+
+// Narrow `span` to exclude surrounding whitespace.
+fn trim_span(line: &str, span: Range<usize>) -> Range<usize> {
+    let text = &line[span.clone()];
+    let start = span.start + (text.len() - text.trim_start().len());
+    let end = span.start + text.trim_end().len();
+    start..end.max(start)
+}
+
+// The span of a raw value, excluding any inline comment.
+fn value_span(line: &str, span: Range<usize>) -> Range<usize> {
+    let trimmed = trim_span(line, span.clone());
+    if is_quoted(&line[trimmed.clone()]) {
+        return trimmed;
+    }
+
+    let mut escaped = false;
+    for (offset, c) in line[span.clone()].char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            ';' | '#' => return trim_span(line, span.start..span.start + offset),
+            _ => {}
+        }
+    }
+    trimmed
+}
+
+// Whether text is wrapped in matching single or double quotes.
+fn is_quoted(text: &str) -> bool {
+    let bytes = text.as_bytes();
+    bytes.len() > 1 && (bytes[0] == b'"' || bytes[0] == b'\'') && bytes[bytes.len() - 1] == bytes[0]
+}
+
+/// Render a key or value so that `parse` reads it back unchanged.
+pub(crate) fn quote(text: &str) -> String {
+    let needs_quotes = text.contains(|c: char| c == ';' || c == '#' || c == '\\' || c.is_control())
+        || text.trim() != text
+        || text.starts_with('"')
+        || text.starts_with('\'');
+    if !needs_quotes {
+        return text.to_string();
+    }
+
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+// Split on `\n` and `\r\n`, keeping blank lines so line numbers stay accurate.
+pub(crate) fn lines(input: &str) -> impl Iterator<Item = &str> {
+    input
+        .split('\n')
+        .map(|line| line.strip_suffix('\r').unwrap_or(line))
+}
+
+// Strip quotes, or inline comments and escapes from unquoted text.
+fn unquote(raw: &str) -> String {
+    let raw = raw.trim();
+    if is_quoted(raw) {
+        let inner = &raw[1..raw.len() - 1];
+        if raw.starts_with('\'') {
+            return inner.to_string();
+        }
+        return unescape_json(inner).unwrap_or_else(|| raw.to_string());
+    }
+
+    let mut out = String::with_capacity(raw.len());
+    let mut chars = raw.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            ';' | '#' => break,
+            '\\' => match chars.next() {
+                Some(next @ '\\') | Some(next @ ';') | Some(next @ '#') => out.push(next),
+                Some(next) => {
+                    out.push('\\');
+                    out.push(next);
+                }
+                None => out.push('\\'),
+            },
+            c => out.push(c),
+        }
+    }
+
+    out.trim().to_string()
+}
+
+// Decode the body of a JSON string literal, or `None` if it isn't one.
+fn unescape_json(inner: &str) -> Option<String> {
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => return None,
+            '\\' => {
+                let decoded = match chars.next()? {
+                    '"' => '"',
+                    '\\' => '\\',
+                    '/' => '/',
+                    'b' => '\u{8}',
+                    'f' => '\u{c}',
+                    'n' => '\n',
+                    'r' => '\r',
+                    't' => '\t',
+                    'u' => {
+                        let hex: String = chars.by_ref().take(4).collect();
+                        let code = u32::from_str_radix(&hex, 16).ok()?;
+                        ::std::char::from_u32(code)?
+                    }
+                    _ => return None,
+                };
+                out.push(decoded);
+            }
+            c => out.push(c),
+        }
+    }
+
+    Some(out)
+}
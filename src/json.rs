@@ -0,0 +1,277 @@
+//! JSON output in the shape of `npm config list --json`, and reading it back.
+
+use serde::de::Error as _;
+use serde::{Serialize, Serializer};
+use serde_json::{self, Map, Number, Value};
+
+use definitions::{definition, Definition, Type, DEFINITIONS};
+use loader::Setting;
+use secret::{is_secret_key, redact};
+use value::coerce;
+use {Error, Layer, Npmrc, Source};
+
+// Keys whose default `to_json` computes from where the config was loaded.
+const COMPUTED: &[&str] = &["globalconfig", "prefix"];
+
+impl Npmrc {
+    /// The effective config as a JSON object, like `npm config list --json`
+    /// prints it.
+    ///
+    /// Every key npm defines is included with its value or default, along
+    /// with every other key that's set. Booleans and numbers are JSON
+    /// booleans and numbers, lists are arrays, and keys that are unset are
+    /// `null`. Like npm, credentials such as `_authToken` are left out, and
+    /// passwords in URLs are masked as `***`.
+    ///
+    /// ```
+    /// # fn main() -> Result<(), npmrc::Error> {
+    /// let npmrc: npmrc::Npmrc = "save=false\n//registry.npmjs.org/:_authToken=npm_abc".parse()?;
+    /// let json = npmrc.to_json();
+    /// assert_eq!(json["save"], false);
+    /// assert_eq!(json["registry"], "https://registry.npmjs.org/");
+    /// assert!(json.get("//registry.npmjs.org/:_authToken").is_none());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn to_json(&self) -> Value {
+        self.json(true)
+    }
+
+    /// Like `to_json`, with credentials included as they're stored.
+    pub fn to_json_unredacted(&self) -> Value {
+        self.json(false)
+    }
+
+    /// Read config from a JSON object like `to_json` or `npm config list
+    /// --json` prints, as user config.
+    ///
+    /// Values may be strings, booleans, numbers or arrays for lists. Keys
+    /// that are `null`, or that have the value `to_json` shows when they're
+    /// unset, are skipped, so only the keys that were set are read back.
+    /// `globalconfig` and `prefix` are always skipped: `to_json` shows where
+    /// they point for the config it was called on, which can't be told apart
+    /// from a value that was set. Like
+    /// with `from_path`, values of the wrong type are skipped and reported by
+    /// `Npmrc::invalid_values`. Output of `to_json` doesn't have credentials,
+    /// so neither does the config read from it.
+    pub fn from_json(json: &str) -> Result<Npmrc, Error> {
+        let object = match serde_json::from_str(json)? {
+            Value::Object(object) => object,
+            _ => return Err(serde_json::Error::custom("expected an object of config keys").into()),
+        };
+
+        let unset = Npmrc::from_user_settings(Vec::new())?;
+        let mut settings = Vec::new();
+        for (key, value) in object {
+            let is_default = definition(&key).is_some_and(|definition| {
+                unset.json_default(definition) == normalize(definition, &value)
+            });
+            if value.is_null() || is_default || COMPUTED.contains(&key.as_str()) {
+                continue;
+            }
+
+            let source = || Source::new(Layer::User);
+            match value {
+                Value::Array(items) => {
+                    for item in items {
+                        let item = scalar(&key, item)?;
+                        settings.push(Setting::new(format!("{}[]", key), item, source()));
+                    }
+                }
+                value => settings.push(Setting::new(key.clone(), scalar(&key, value)?, source())),
+            }
+        }
+        Npmrc::from_user_settings(settings)
+    }
+
+    fn json(&self, redacted: bool) -> Value {
+        let mut json = Map::new();
+        for definition in DEFINITIONS {
+            json.insert(definition.key.to_string(), self.json_default(definition));
+        }
+        for key in self.sources.keys() {
+            let value = match self.lists.get(key) {
+                Some(values) => {
+                    let definition = definition(key);
+                    let mut values: Vec<Value> = values
+                        .iter()
+                        .map(|value| typed(definition, value))
+                        .collect();
+                    if values.len() == 1 && !definition.is_some_and(|def| def.multiple) {
+                        values.remove(0)
+                    } else {
+                        Value::Array(values)
+                    }
+                }
+                // The winning assignment unset the key.
+                None => Value::Null,
+            };
+            json.insert(key.clone(), value);
+        }
+
+        if redacted {
+            json.retain(|key, _| !is_secret_key(key));
+            for (key, value) in json.iter_mut() {
+                mask(key, value);
+            }
+        }
+        Value::Object(json)
+    }
+
+    // The value npm would show for a key that isn't set.
+    fn json_default(&self, definition: &Definition) -> Value {
+        // Keep in line with `COMPUTED`.
+        let computed = match definition.key {
+            "globalconfig" => self.globalconfig(),
+            "prefix" => self.prefix(),
+            _ => None,
+        };
+        if let Some(path) = computed {
+            return Value::from(path.to_string_lossy().into_owned());
+        }
+
+        // Coerce the default like a value, so paths like `~/.npm` resolve.
+        let value = definition
+            .default
+            .and_then(|default| coerce(definition, default, &self.context).ok()?);
+        match value {
+            Some(value) if definition.multiple => {
+                Value::Array(vec![typed(Some(definition), &value)])
+            }
+            Some(value) => typed(Some(definition), &value),
+            None => Value::Null,
+        }
+    }
+}
+
+/// Serializes the effective config like `Npmrc::to_json`, with credentials
+/// left out.
+impl Serialize for Npmrc {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_json().serialize(serializer)
+    }
+}
+
+// A stored value as the JSON type of its key.
+fn typed(definition: Option<&Definition>, value: &str) -> Value {
+    match definition.map(|definition| definition.ty) {
+        Some(Type::Boolean) => Value::Bool(value == "true"),
+        Some(Type::Number) | Some(Type::Umask) => value
+            .parse::<i64>()
+            .map(Value::from)
+            .ok()
+            .or_else(|| {
+                let number = value.parse::<f64>().ok()?;
+                Number::from_f64(number).map(Value::Number)
+            })
+            .unwrap_or_else(|| Value::from(value)),
+        _ => Value::from(value),
+    }
+}
+
+// A value read from JSON in the form `to_json` writes it, so it can be
+// compared with a default.
+fn normalize(definition: &Definition, value: &Value) -> Value {
+    let item = |value: &Value| match *value {
+        Value::String(ref text) => typed(Some(definition), text),
+        Value::Bool(_) | Value::Number(_) => typed(Some(definition), &value.to_string()),
+        ref value => value.clone(),
+    };
+    match *value {
+        Value::Array(ref items) => Value::Array(items.iter().map(item).collect()),
+        ref value if definition.multiple && !value.is_null() => Value::Array(vec![item(value)]),
+        ref value => item(value),
+    }
+}
+
+// Mask passwords in URL values.
+fn mask(key: &str, value: &mut Value) {
+    match *value {
+        Value::String(ref mut text) => {
+            let masked = redact(key, text).into_owned();
+            *text = masked;
+        }
+        Value::Array(ref mut items) => {
+            for item in items {
+                mask(key, item);
+            }
+        }
+        _ => {}
+    }
+}
+
+// A JSON value as it would be written in `.npmrc`.
+fn scalar(key: &str, value: Value) -> Result<String, Error> {
+    match value {
+        Value::String(text) => Ok(text),
+        Value::Null => Ok("null".to_string()),
+        Value::Bool(_) | Value::Number(_) => Ok(value.to_string()),
+        _ => {
+            let message = format!("expected a string, number, boolean or null for `{}`", key);
+            Err(serde_json::Error::custom(message).into())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {Loader, MemoryFs};
+
+    use super::*;
+
+    #[test]
+    fn round_trips_through_json() {
+        let npmrc: Npmrc = "\
+registry=https://npm.example.com/
+@myorg:registry=https://myorg.example.com/
+save=false
+save-exact=true
+fetch-retries=5
+umask=0o027
+loglevel=warn
+omit[]=dev
+omit[]=peer
+ca[]=one
+init-author-name=Me
+some-unknown-key=value
+"
+        .parse()
+        .unwrap();
+
+        let json = npmrc.to_json();
+        let back = Npmrc::from_json(&json.to_string()).unwrap();
+        assert_eq!(back.to_json(), json);
+        assert_eq!(back.keys(), npmrc.keys());
+        assert!(back.invalid_values().is_empty());
+        assert_eq!(back.list("omit"), ["dev", "peer"]);
+        assert_eq!(back.source("omit").unwrap().layer, Layer::User);
+    }
+
+    #[test]
+    fn skips_defaults_and_computed_keys() {
+        // Paths like `cache` default to the same home as `from_json` uses,
+        // while `prefix` and the global config come from the loader.
+        let home = dirs::home_dir().unwrap();
+        let fs = MemoryFs::new().file(home.join(".npmrc"), "save-exact=true\n");
+        let npmrc = Loader::new()
+            .fs(fs)
+            .cwd("/repo")
+            .env(vec![
+                ("HOME", home.to_str().unwrap()),
+                ("PREFIX", "/opt/node"),
+            ])
+            .load()
+            .unwrap();
+        let json = npmrc.to_json();
+        assert_eq!(json["prefix"], "/opt/node");
+        assert_eq!(json["globalconfig"], "/opt/node/etc/npmrc");
+
+        let back = Npmrc::from_json(&json.to_string()).unwrap();
+        assert_eq!(back.keys(), ["save-exact"]);
+
+        let back =
+            Npmrc::from_json(r#"{"save": true, "tag": "latest", "preid": null, "prefix": "/x"}"#)
+                .unwrap();
+        assert!(back.keys().is_empty());
+    }
+}
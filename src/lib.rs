@@ -2,26 +2,87 @@
 //!
 //! ## Usage
 //!
-//! ```rust,ignore
+//! ```no_run
 //! extern crate npmrc;
 //! let npmrc_values = npmrc::read().unwrap();
 //! println!("{:?}", npmrc_values);
 //! ```
-#[macro_use(format_err)]
-extern crate failure;
+//!
+//! To see the same configuration npm itself would use in the current
+//! directory, merge every layer of npm's config cascade with `load()`:
+//!
+//! ```no_run
+//! let npmrc_values = npmrc::load().unwrap();
+//! ```
+extern crate base64;
 extern crate serde;
+extern crate serde_json;
 #[macro_use(Deserialize)]
 extern crate serde_derive;
-extern crate serde_ini;
+extern crate url;
 
-use failure::Error;
+#[cfg(feature = "rustls")]
+extern crate rustls;
+#[cfg(feature = "rustls")]
+extern crate webpki_roots;
+
+use serde::de::value::MapDeserializer;
 use serde::{de, Deserialize, Deserializer};
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
 use std::fs;
+use std::io;
+use std::path::Path;
 use std::str::FromStr;
 
-// `serde_ini` only supports serializing to string types, so we have to define
-// a custom deserializer.
+mod accessors;
+mod credentials;
+mod definitions;
+mod document;
+mod env;
+mod error;
+mod filesystem;
+mod ini;
+mod json;
+mod lint;
+mod loader;
+mod project;
+mod proxy;
+mod registry;
+mod scope;
+mod secret;
+mod source;
+mod spec;
+mod tls;
+mod value;
+
+use loader::{Context, Setting};
+use proxy::ProxyEnv;
+
+pub use credentials::{nerf_dart, RegistryCredentials};
+pub use definitions::{definition, Definition, Type, DEFINITIONS};
+pub use document::Document;
+pub use env::{env_config, UnresolvedEnv};
+pub use error::Error;
+pub use filesystem::{FileSystem, MemoryFs, RealFs};
+pub use lint::{Diagnostic, Lint};
+pub use loader::{load, Layer, Loader};
+pub use project::{Project, ProjectLocator, ProjectReason};
+pub use proxy::ProxyConfig;
+pub use registry::{Registry, ABBREVIATED_ACCEPT};
+pub use scope::Scope;
+pub use secret::{is_secret_key, redact, Secret};
+pub use source::{Assignment, Explanation, Source};
+pub use spec::{PackageSpec, Resolved, SpecError, SpecKind};
+pub use tls::{Certificate, ClientIdentity, KeyFormat, PrivateKey, TlsConfig};
+pub use value::InvalidValue;
+
+/// The registry npm uses when none is configured.
+pub const DEFAULT_REGISTRY: &str = "https://registry.npmjs.org/";
+
+// Values in `.npmrc` are always strings, so we have to define a custom
+// deserializer.
 fn de_from_str<'de, D>(deserializer: D) -> Result<bool, D::Error>
 where
     D: Deserializer<'de>,
@@ -30,8 +91,57 @@ where
     bool::from_str(&s).map_err(de::Error::custom)
 }
 
+// npm's default for the boolean fields that are on unless configured
+// otherwise, matching their `DEFINITIONS` entries.
+fn default_true() -> bool {
+    true
+}
+
+// Deserialize a value through its `FromStr` implementation.
+fn de_parse<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    let s = String::deserialize(deserializer)?;
+    s.parse().map_err(de::Error::custom)
+}
+
+// Like `de_parse`, for fields that are unset by default.
+fn de_parse_some<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    de_parse(deserializer).map(Some)
+}
+
+/// The error returned when a value isn't one of the spellings npm accepts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseEnumError {
+    what: &'static str,
+    value: String,
+    expected: &'static [&'static str],
+}
+
+impl fmt::Display for ParseEnumError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "invalid {} `{}`, expected one of: {}",
+            self.what,
+            self.value,
+            self.expected.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for ParseEnumError {}
+
 /// Npm's access levels.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Access {
     /// Access is public.
     Public,
@@ -40,85 +150,172 @@ pub enum Access {
     Restricted,
 }
 
-/// Npm's log levels.
-#[derive(Debug, Deserialize)]
+impl Access {
+    const NAMES: &'static [&'static str] = &["public", "restricted"];
+}
+
+impl FromStr for Access {
+    type Err = ParseEnumError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "public" => Ok(Access::Public),
+            "restricted" => Ok(Access::Restricted),
+            _ => Err(ParseEnumError {
+                what: "access level",
+                value: s.to_string(),
+                expected: Access::NAMES,
+            }),
+        }
+    }
+}
+
+impl fmt::Display for Access {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(Access::NAMES[*self as usize])
+    }
+}
+
+/// Deserializes the spellings `FromStr` accepts.
+impl<'de> Deserialize<'de> for Access {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        de_parse(deserializer)
+    }
+}
+
+/// Npm's log levels, ordered from least to most verbose.
+///
+/// A level enables logging of every level before it:
+///
+/// ```
+/// use npmrc::LogLevel;
+///
+/// assert!(LogLevel::Verbose.enables(LogLevel::Http));
+/// assert!(!LogLevel::Notice.enables(LogLevel::Http));
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum LogLevel {
     /// No messages.
-    #[serde(rename = "silent")]
     Silent,
 
     /// Log out error messages.
-    #[serde(rename = "error")]
     Error,
 
     /// Log out warnings.
-    #[serde(rename = "warn")]
     Warn,
 
     /// Log out notices.
-    #[serde(rename = "notice")]
+    #[default]
     Notice,
 
     /// Log out HTTP requests.
-    #[serde(rename = "http")]
     Http,
 
     /// Log out timing information.
-    #[serde(rename = "timing")]
     Timing,
 
     /// Log out a balanced amount of information.
-    #[serde(rename = "info")]
     Info,
 
     /// Log out most things.
-    #[serde(rename = "verbose")]
     Verbose,
 
     /// Log out everything.
-    #[serde(rename = "silly")]
     Silly,
 }
 
-#[derive(Debug, Deserialize)]
-pub struct Scope {
-    name: String,
-    registry_url: String,
+impl LogLevel {
+    const ALL: [LogLevel; 9] = [
+        LogLevel::Silent,
+        LogLevel::Error,
+        LogLevel::Warn,
+        LogLevel::Notice,
+        LogLevel::Http,
+        LogLevel::Timing,
+        LogLevel::Info,
+        LogLevel::Verbose,
+        LogLevel::Silly,
+    ];
+
+    const NAMES: &'static [&'static str] = &[
+        "silent", "error", "warn", "notice", "http", "timing", "info", "verbose", "silly",
+    ];
+
+    /// Whether messages logged at `level` are shown at this log level.
+    pub fn enables(self, level: LogLevel) -> bool {
+        level != LogLevel::Silent && level <= self
+    }
+}
+
+impl FromStr for LogLevel {
+    type Err = ParseEnumError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        LogLevel::ALL
+            .iter()
+            .zip(LogLevel::NAMES)
+            .find(|&(_, name)| *name == s)
+            .map(|(level, _)| *level)
+            .ok_or_else(|| ParseEnumError {
+                what: "log level",
+                value: s.to_string(),
+                expected: LogLevel::NAMES,
+            })
+    }
+}
+
+impl fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(LogLevel::NAMES[*self as usize])
+    }
+}
+
+/// Deserializes the spellings `FromStr` accepts.
+impl<'de> Deserialize<'de> for LogLevel {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        de_parse(deserializer)
+    }
 }
 
 /// Representation of `.npmrc`.
-#[derive(Debug, Deserialize)]
+///
+/// `Debug` output masks credentials such as `_authToken` as `***`, and
+/// serializing leaves them out, like `Npmrc::to_json`.
+#[derive(Deserialize)]
 pub struct Npmrc {
     /// When publishing scoped packages, the access level defaults to `restricted`.
     /// If you want your scoped package to be publicly viewable (and installable)
     /// set `--access=public`. The only valid values for `access` are `public` and
     /// `restricted`. Unscoped packages always have an access level of `public`.
     /// [Read More.](https://docs.npmjs.com/misc/config#access)
-    #[serde(default)]
-    pub access: String,
+    #[serde(default, deserialize_with = "de_parse_some")]
+    pub access: Option<Access>,
 
-    /// Set npm's log level.
-    #[serde(default)]
-    pub loglevel: String,
+    /// Set npm's log level. Defaults to `notice`.
+    #[serde(default, deserialize_with = "de_parse")]
+    pub loglevel: LogLevel,
 
-    /// Should npm echo out progress while installing packages?
-    #[serde(default, deserialize_with = "de_from_str")]
+    /// Should npm echo out progress while installing packages? Defaults to
+    /// `true`.
+    #[serde(default = "default_true", deserialize_with = "de_from_str")]
     pub progress: bool,
 
-    /// Should npm create a package-lock.json file?
+    /// Should npm create a package-lock.json file? Defaults to `true`.
     #[serde(rename = "package-lock")]
-    #[serde(default, deserialize_with = "de_from_str")]
+    #[serde(default = "default_true", deserialize_with = "de_from_str")]
     pub package_lock: bool,
 
-    /// The base URL of the npm registry.
+    /// The base URL of the npm registry. Empty when it isn't configured, in
+    /// which case lookups like `registry_for` use `DEFAULT_REGISTRY`.
     #[serde(default)]
     pub registry: String,
 
-    /// Should npm modify package.json when installing?
-    #[serde(default, deserialize_with = "de_from_str")]
+    /// Should npm modify package.json when installing? Defaults to `true`.
+    #[serde(default = "default_true", deserialize_with = "de_from_str")]
     pub save: bool,
 
-    #[serde(default)]
+    /// Scopes mapped to their own registry with `@scope:registry`.
+    #[serde(skip)]
     pub scopes: Vec<Scope>,
 
     /// The value `npm init` should use by default for the package author's name.
@@ -131,47 +328,314 @@ pub struct Npmrc {
 
     #[serde(flatten)]
     other: HashMap<String, String>,
+
+    #[serde(skip)]
+    sources: HashMap<String, Vec<Assignment>>,
+
+    #[serde(skip)]
+    lists: HashMap<String, Vec<String>>,
+
+    #[serde(skip)]
+    invalid: Vec<InvalidValue>,
+
+    #[serde(skip)]
+    unresolved: Vec<UnresolvedEnv>,
+
+    #[serde(skip)]
+    context: Context,
+}
+
+impl fmt::Debug for Npmrc {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let other: BTreeMap<&str, Cow<str>> = self
+            .other
+            .iter()
+            .map(|(key, value)| (key.as_str(), secret::redact(key, value)))
+            .collect();
+        f.debug_struct("Npmrc")
+            .field("access", &self.access)
+            .field("loglevel", &self.loglevel)
+            .field("progress", &self.progress)
+            .field("package_lock", &self.package_lock)
+            .field("registry", &secret::redact("registry", &self.registry))
+            .field("save", &self.save)
+            .field("scopes", &self.scopes)
+            .field("init_author_name", &self.init_author_name)
+            .field("init_author_email", &self.init_author_email)
+            .field("other", &other)
+            .finish_non_exhaustive()
+    }
 }
 
 impl Npmrc {
-    pub fn get_registry_for_package(&self, package: &str) -> Option<&str> {
-        for scope in &self.scopes {
-            if package.starts_with(format!("@{}/", scope.name).as_str()) {
-                return Some(&scope.registry_url);
+    // Build an `Npmrc` from settings ordered from lowest to highest precedence.
+    fn from_settings(settings: Vec<Setting>, context: Context) -> Result<Npmrc, Error> {
+        let mut values = HashMap::new();
+        let mut sources: HashMap<String, Vec<Assignment>> = HashMap::new();
+        let mut lists: HashMap<String, Vec<String>> = HashMap::new();
+        let mut invalid = Vec::new();
+
+        for mut setting in settings {
+            if let Some(definition) = definition(&setting.key) {
+                match value::coerce(definition, &setting.value, &context) {
+                    Ok(Some(value)) => setting.value = value,
+                    Ok(None) => {
+                        // Keep the assignment, so it shows up in `explain` and
+                        // `false` can be told apart from unset.
+                        let assignments = sources.entry(setting.key.clone()).or_default();
+                        for assignment in assignments.iter_mut() {
+                            assignment.active = false;
+                        }
+                        assignments.push(Assignment {
+                            value: setting.value,
+                            source: setting.source,
+                            active: true,
+                            append: setting.append,
+                        });
+                        values.remove(&setting.key);
+                        lists.remove(&setting.key);
+                        continue;
+                    }
+                    Err(expected) => {
+                        invalid.push(InvalidValue {
+                            key: setting.key,
+                            value: setting.value,
+                            expected,
+                            source: setting.source,
+                        });
+                        continue;
+                    }
+                }
+            }
+
+            // Like npm, `key[]=` adds to the values set earlier in the same
+            // file, while a later layer replaces the whole list.
+            let assignments = sources.entry(setting.key.clone()).or_default();
+            let extends = setting.append
+                && assignments.last().is_some_and(|last| {
+                    last.source.layer == setting.source.layer
+                        && last.source.path == setting.source.path
+                });
+            let list = lists.entry(setting.key.clone()).or_default();
+            if !extends {
+                for assignment in assignments.iter_mut() {
+                    assignment.active = false;
+                }
+                list.clear();
             }
+            list.push(setting.value.clone());
+            assignments.push(Assignment {
+                value: setting.value.clone(),
+                source: setting.source,
+                active: true,
+                append: setting.append,
+            });
+            values.insert(setting.key, setting.value);
         }
 
+        let deserializer = MapDeserializer::<_, de::value::Error>::new(values.into_iter());
+        // Every typed field has a definition, so its value was coerced above.
+        let mut contents =
+            Npmrc::deserialize(deserializer).expect("config values are coerced before use");
+        contents.sources = sources;
+        contents.lists = lists;
+        contents.invalid = invalid;
+        contents.context = context;
+        contents.collect_scopes();
+        Ok(contents)
+    }
+
+    /// Values that were ignored because they don't have the type npm expects
+    /// for their key.
+    pub fn invalid_values(&self) -> &[InvalidValue] {
+        &self.invalid
+    }
+
+    /// References to environment variables that aren't set. Like npm, they
+    /// are kept in values as written, like `${NPM_TOKEN}`.
+    pub fn unresolved_env(&self) -> &[UnresolvedEnv] {
+        &self.unresolved
+    }
+
+    /// The project the project config was read for, or `None` for config
+    /// from `read()`, which only reads the user config.
+    pub fn project(&self) -> Option<&Project> {
+        self.context.project.as_ref()
+    }
+
+    /// Merge an `npm_config_*` environment layer over this config.
+    ///
+    /// Values from `vars` take precedence over file-based values and replace
+    /// any environment layer this config was loaded with. See
+    /// `env_config` for how variable names are turned into keys. Like with
+    /// `Loader`, `${VAR}` references in the layer are expanded against
+    /// `vars`.
+    pub fn merge_env<I, K, V>(self, vars: I) -> Result<Npmrc, Error>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: Into<String>,
+    {
+        let vars: HashMap<String, String> = vars
+            .into_iter()
+            .map(|(name, value)| (name.as_ref().to_string(), value.into()))
+            .collect();
+        let (env, env_unresolved) = env::env_layer(&vars, true);
+
+        let mut settings = self.settings();
+        settings.retain(|setting| setting.source.layer != Layer::Env);
+        settings.extend(env);
+        settings.sort_by_key(|setting| setting.source.layer);
+        let mut npmrc = Npmrc::from_settings(settings, self.context.clone())?;
+
+        npmrc.unresolved = self.unresolved;
+        npmrc
+            .unresolved
+            .retain(|unresolved| unresolved.source.layer != Layer::Env);
+        npmrc.unresolved.extend(env_unresolved);
+        npmrc
+            .unresolved
+            .sort_by_key(|unresolved| unresolved.source.layer);
+        Ok(npmrc)
+    }
+
+    // Every assignment this config was built from, as settings.
+    fn settings(&self) -> Vec<Setting> {
+        let mut settings: Vec<Setting> = self
+            .invalid
+            .iter()
+            .map(|invalid| Setting {
+                key: invalid.key.clone(),
+                value: invalid.value.clone(),
+                source: invalid.source.clone(),
+                append: false,
+            })
+            .collect();
+        for (key, assignments) in &self.sources {
+            for assignment in assignments {
+                settings.push(Setting {
+                    key: key.clone(),
+                    value: assignment.value.clone(),
+                    source: assignment.source.clone(),
+                    append: assignment.append,
+                });
+            }
+        }
+        settings
+    }
+
+    /// Where the resolved value of `key` was set, if it was set at all.
+    pub fn source(&self, key: &str) -> Option<&Source> {
+        self.sources
+            .get(key)
+            .and_then(|assignments| assignments.iter().find(|assignment| assignment.active))
+            .map(|assignment| &assignment.source)
+    }
+
+    /// List every layer that assigned `key`, marking the one that won.
+    pub fn explain(&self, key: &str) -> Explanation {
+        Explanation {
+            key: key.to_string(),
+            assignments: self.sources.get(key).cloned().unwrap_or_default(),
+        }
+    }
+
+    // The registry packages are fetched from when no scope applies.
+    fn default_registry(&self) -> &str {
         if self.registry.is_empty() {
-            None
+            DEFAULT_REGISTRY
         } else {
-            Some(&self.registry)
+            &self.registry
         }
     }
+
+    /// The registry `package` is fetched from: its scope's registry if one is
+    /// configured, otherwise the default registry.
+    ///
+    /// `package` can be any specifier `PackageSpec` parses. Returns `None` if
+    /// it is invalid or doesn't come from a registry.
+    pub fn get_registry_for_package(&self, package: &str) -> Option<&str> {
+        let spec = PackageSpec::parse(package).ok()?;
+        spec.registry_name()
+            .map(|name| self.registry_for_name(name))
+    }
+
+    // The registry a package with this registry name is fetched from.
+    fn registry_for_name(&self, name: &str) -> &str {
+        self.scopes
+            .iter()
+            .find(|scope| scope.contains(name))
+            .map_or(self.default_registry(), |scope| &scope.registry_url)
+    }
 }
 
 /// Read out `.npmrc` and return it.
+///
+/// This is the user config: `~/.npmrc`, or the file `npm_config_userconfig`
+/// points at. `${VAR}` references are expanded from the process environment,
+/// and ones to unset variables are kept as written and reported by
+/// `Npmrc::unresolved_env`. Values of the wrong type are skipped and reported
+/// by `Npmrc::invalid_values`.
 pub fn read() -> Result<Npmrc, Error> {
-    let npmrc_path = match dirs::home_dir() {
-        None => return Err(format_err!("User's home directory not found")),
-        Some(home_path) => home_path.join(".npmrc"),
-    };
+    let home = dirs::home_dir();
+    match env_config(std::env::vars()).remove("userconfig") {
+        Some(path) => {
+            let cwd = std::env::current_dir()?;
+            Npmrc::from_path(value::resolve_path(&path, home.as_deref(), &cwd))
+        }
+        None => match home {
+            Some(home) => Npmrc::from_path(home.join(".npmrc")),
+            None => Err(Error::HomeDirNotFound),
+        },
+    }
+}
 
-    let npmrc = fs::read_to_string(npmrc_path)?;
+impl Npmrc {
+    /// Read a single `.npmrc` file as user config, without any other layer.
+    ///
+    /// Like `read()`, `${VAR}` references are expanded from the process
+    /// environment and relative paths resolve against the current directory
+    /// and home directory. Use `Loader` to control those as well.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Npmrc, Error> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path).map_err(|err| Error::io(path, err))?;
+        Npmrc::from_contents(Some(path), &contents)
+    }
 
-    let mut contents: Npmrc = serde_ini::from_str(&npmrc)?;
+    /// Read the contents of an `.npmrc` file from `reader`, like `from_path`.
+    ///
+    /// Diagnostics locate lines in the contents as `<input>`.
+    pub fn from_reader<R: io::Read>(mut reader: R) -> Result<Npmrc, Error> {
+        let mut contents = String::new();
+        reader.read_to_string(&mut contents)?;
+        Npmrc::from_contents(None, &contents)
+    }
 
-    for (key, value) in &contents.other {
-        if key.starts_with('@') {
-            let name = key.split(':').next().unwrap();
-            let registry_url = value;
+    fn from_contents(path: Option<&Path>, contents: &str) -> Result<Npmrc, Error> {
+        let mut settings = loader::parse_settings(Layer::User, path, contents)?;
+        let unresolved = env::expand_settings(&mut settings, |name| std::env::var(name).ok());
+        let mut npmrc = Npmrc::from_user_settings(settings)?;
+        npmrc.unresolved = unresolved;
+        Ok(npmrc)
+    }
 
-            let scope = Scope {
-                name: name.to_string(),
-                registry_url: registry_url.to_string(),
-            };
-            contents.scopes.push(scope);
-        }
+    // Build user config, resolving it against the process' directories.
+    fn from_user_settings(settings: Vec<Setting>) -> Result<Npmrc, Error> {
+        let context = Context {
+            home: dirs::home_dir(),
+            cwd: std::env::current_dir()?,
+            proxy_env: ProxyEnv::new(|name| std::env::var(name).ok()),
+            ..Context::default()
+        };
+        Npmrc::from_settings(settings, context)
     }
+}
 
-    Ok(contents)
+/// Parses the contents of an `.npmrc` file, like `Npmrc::from_reader`.
+impl FromStr for Npmrc {
+    type Err = Error;
+
+    fn from_str(contents: &str) -> Result<Self, Self::Err> {
+        Npmrc::from_contents(None, contents)
+    }
 }
@@ -0,0 +1,870 @@
+//! Resolve npm's full configuration cascade into a single `Npmrc`.
+
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use definitions::definition;
+use env::{env_layer, expand_settings};
+use filesystem::{FileSystem, RealFs};
+use ini;
+use project::{Project, ProjectLocator};
+use proxy::ProxyEnv;
+use value::{coerce, coerce_bool, resolve_path};
+use {Error, Npmrc, Source, UnresolvedEnv};
+
+/// The layers npm reads its configuration from, ordered from lowest to
+/// highest precedence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Layer {
+    /// The `npmrc` file shipped inside the npm installation.
+    Builtin,
+
+    /// The global config file: `globalconfig`, or `$PREFIX/etc/npmrc`.
+    Global,
+
+    /// The user config file: `userconfig`, or `~/.npmrc`.
+    User,
+
+    /// The `.npmrc` file at the root of the current project.
+    Project,
+
+    /// `npm_config_*` environment variables.
+    Env,
+
+    /// Explicit overrides, the equivalent of flags passed on npm's command line.
+    Cli,
+}
+
+impl fmt::Display for Layer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            Layer::Builtin => "builtin",
+            Layer::Global => "global",
+            Layer::User => "user",
+            Layer::Project => "project",
+            Layer::Env => "env",
+            Layer::Cli => "cli",
+        };
+        f.write_str(name)
+    }
+}
+
+/// A single key assigned in one of the layers.
+#[derive(Debug, Clone)]
+pub(crate) struct Setting {
+    pub key: String,
+    pub value: String,
+    pub source: Source,
+    /// Whether the key was written as `key[]`, adding to a list.
+    pub append: bool,
+}
+
+impl Setting {
+    /// A setting for `key` as written, with any `[]` suffix split off.
+    pub fn new(key: String, value: String, source: Source) -> Self {
+        let (key, append) = match key.strip_suffix("[]") {
+            Some(key) if !key.is_empty() => (key.to_string(), true),
+            _ => (key, false),
+        };
+        Setting {
+            key,
+            value,
+            source,
+            append,
+        }
+    }
+}
+
+/// What values are resolved against: the directories paths are relative to,
+/// the proxy variables npm falls back on and the project config was loaded
+/// for.
+#[derive(Debug, Clone)]
+pub(crate) struct Context {
+    pub home: Option<PathBuf>,
+    pub cwd: PathBuf,
+    pub proxy_env: ProxyEnv,
+    pub project: Option<Project>,
+    /// The global prefix npm derives from the Node.js location, used when
+    /// `prefix` isn't set.
+    pub prefix: Option<PathBuf>,
+    /// Where files the config points at, like `cafile`, are read from.
+    pub fs: Arc<dyn FileSystem>,
+}
+
+impl Default for Context {
+    fn default() -> Self {
+        Context {
+            home: None,
+            cwd: PathBuf::new(),
+            proxy_env: ProxyEnv::default(),
+            project: None,
+            prefix: None,
+            fs: Arc::new(RealFs),
+        }
+    }
+}
+
+/// Loads every configuration layer npm would read and merges them.
+///
+/// ```
+/// # fn main() -> Result<(), npmrc::Error> {
+/// let npmrc = npmrc::Loader::new()
+///     .cwd("/path/to/project")
+///     .set("registry", "https://registry.example.com/")
+///     .load()?;
+/// assert_eq!(npmrc.registry, "https://registry.example.com/");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Loader {
+    cwd: Option<PathBuf>,
+    home: Option<PathBuf>,
+    env: Option<HashMap<String, String>>,
+    fs: Arc<dyn FileSystem>,
+    overrides: Vec<(String, String)>,
+    expand_env: bool,
+    strict: bool,
+}
+
+impl Default for Loader {
+    fn default() -> Self {
+        Loader {
+            cwd: None,
+            home: None,
+            env: None,
+            fs: Arc::new(RealFs),
+            overrides: Vec::new(),
+            expand_env: true,
+            strict: false,
+        }
+    }
+}
+
+impl Loader {
+    /// Create a loader that starts from the process' current directory.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the directory the project `.npmrc` is searched from.
+    pub fn cwd<P: Into<PathBuf>>(mut self, cwd: P) -> Self {
+        self.cwd = Some(cwd.into());
+        self
+    }
+
+    /// Set the home directory the user `.npmrc` and `~/` paths are found in.
+    ///
+    /// Defaults to `$HOME` from `Loader::env` if that's set, and otherwise to
+    /// the current user's home directory.
+    pub fn home<P: Into<PathBuf>>(mut self, home: P) -> Self {
+        self.home = Some(home.into());
+        self
+    }
+
+    /// Use `vars` instead of the process environment.
+    ///
+    /// This covers the `npm_config_*` layer, `${VAR}` expansion, the proxy
+    /// variables and the variables used to locate npm's own config files,
+    /// including `HOME`.
+    pub fn env<I, K, V>(mut self, vars: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        let vars = vars.into_iter().map(|(k, v)| (k.into(), v.into()));
+        self.env = Some(vars.collect());
+        self
+    }
+
+    /// Read files from `fs` instead of the real filesystem.
+    ///
+    /// Together with `cwd`, `home` and `env`, this makes loading hermetic:
+    ///
+    /// ```
+    /// # fn main() -> Result<(), npmrc::Error> {
+    /// let fs = npmrc::MemoryFs::new().file("/home/me/.npmrc", "save=false");
+    /// let npmrc = npmrc::Loader::new()
+    ///     .fs(fs)
+    ///     .cwd("/work")
+    ///     .env(vec![("HOME", "/home/me")])
+    ///     .load()?;
+    /// assert!(!npmrc.save);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn fs<F: FileSystem + 'static>(mut self, fs: F) -> Self {
+        self.fs = Arc::new(fs);
+        self
+    }
+
+    /// Set a value that takes precedence over every other layer.
+    ///
+    /// Like on npm's command line, a key written as `key[]` adds to a list
+    /// instead of replacing it.
+    pub fn set<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Self {
+        self.overrides.push((key.into(), value.into()));
+        self
+    }
+
+    /// Whether to expand `${VAR}` references in keys and values, like npm.
+    ///
+    /// Enabled by default. Like npm, a reference to an unset variable is kept
+    /// as written, unless it is written as `${VAR?}`, and listed by
+    /// `Npmrc::unresolved_env`.
+    pub fn expand_env(mut self, expand: bool) -> Self {
+        self.expand_env = expand;
+        self
+    }
+
+    /// Whether a value of the wrong type, or a reference to an unset
+    /// environment variable, fails loading.
+    ///
+    /// Disabled by default, in which case such values are skipped like npm
+    /// does and listed by `Npmrc::invalid_values`, and references are kept
+    /// and listed by `Npmrc::unresolved_env`. When enabled, the first of
+    /// them is returned as an `Error::UnresolvedEnv` or `Error::InvalidValue`.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// The config files npm would read, from lowest to highest precedence.
+    ///
+    /// Files are listed whether or not they exist. Like npm, the builtin and
+    /// project config, `npm_config_*` variables and overrides can move the
+    /// user config with `userconfig`, and all but the global config can move
+    /// the global config with `globalconfig` or `prefix`, so the files are
+    /// read to find out. A file that can't be read or parsed is still listed,
+    /// and treated as empty when looking for those keys.
+    pub fn files(&self) -> Result<Vec<(Layer, PathBuf)>, Error> {
+        let context = self.context(self.project()?)?;
+        let (later, _) = self.env_and_overrides();
+        let (files, _) = self.read_files(&context, &later, false)?;
+        Ok(files
+            .into_iter()
+            .map(|(layer, path, _)| (layer, path))
+            .collect())
+    }
+
+    /// The project whose `.npmrc` is the project layer.
+    ///
+    /// Like npm, a `prefix` set with `Loader::set` or `npm_config_prefix` is
+    /// the project, and no workspace root is looked for when `workspaces` is
+    /// set to `false`, or `global` is set, in either place.
+    pub fn project(&self) -> Result<Project, Error> {
+        let cwd = self.working_dir()?;
+        let (settings, _) = self.env_and_overrides();
+        // Overrides come after the environment, so they win.
+        let value = |key: &str| {
+            settings
+                .iter()
+                .rev()
+                .find(|setting| setting.key == key)
+                .map(|setting| setting.value.as_str())
+        };
+
+        let global =
+            value("global").is_some_and(coerce_bool) || value("location") == Some("global");
+        let workspaces = value("workspaces").is_none_or(coerce_bool) && !global;
+        let mut locator = ProjectLocator::new(cwd.clone())
+            .workspaces(workspaces)
+            .fs(self.fs.clone());
+        if let Some(prefix) = value("prefix") {
+            locator = locator.prefix(resolve_path(prefix, self.home_dir().as_deref(), &cwd));
+        }
+        Ok(locator.locate())
+    }
+
+    // Read the config files in the order npm does, since each can move the
+    // ones read after it, and return them from lowest to highest precedence
+    // along with the references to unset variables in them. Unless `fail` is
+    // set, files that can't be read or parsed are read as empty.
+    fn read_files(
+        &self,
+        context: &Context,
+        later: &[Setting],
+        fail: bool,
+    ) -> Result<(Vec<File>, Vec<UnresolvedEnv>), Error> {
+        let mut files = Vec::new();
+        let mut unresolved = Vec::new();
+        let mut read = |layer, path: PathBuf| -> Result<File, Error> {
+            let mut settings = match read_file(&*self.fs, layer, &path) {
+                Err(_) if !fail => Vec::new(),
+                settings => settings?,
+            };
+            if self.expand_env {
+                unresolved.extend(expand_settings(&mut settings, |name| self.var(name)));
+            }
+            Ok((layer, path, settings))
+        };
+
+        if let Some(npm_root) = self.npm_root() {
+            files.push(read(Layer::Builtin, npm_root.join("npmrc"))?);
+        }
+
+        let user = |files: &[File]| {
+            configured_path("userconfig", files, later, context)
+                .or_else(|| context.home.as_ref().map(|home| home.join(".npmrc")))
+        };
+
+        // npm refuses to load the same file as both user and project config.
+        let project = context.project.as_ref().map(Project::npmrc);
+        if let Some(project) = project {
+            if user(&files).as_ref() != Some(&project) {
+                files.push(read(Layer::Project, project)?);
+            }
+        }
+
+        if let Some(user) = user(&files) {
+            files.push(read(Layer::User, user)?);
+        }
+
+        let global = configured_path("globalconfig", &files, later, context).or_else(|| {
+            configured_path("prefix", &files, later, context)
+                .or_else(|| context.prefix.clone())
+                .map(|prefix| prefix.join("etc").join("npmrc"))
+        });
+        if let Some(global) = global {
+            files.push(read(Layer::Global, global)?);
+        }
+
+        files.sort_by_key(|&(layer, _, _)| layer);
+        unresolved.sort_by_key(|unresolved| unresolved.source.layer);
+        Ok((files, unresolved))
+    }
+
+    /// Read and merge all layers.
+    pub fn load(&self) -> Result<Npmrc, Error> {
+        let context = self.context(self.project()?)?;
+        let (later, later_unresolved) = self.env_and_overrides();
+        let (files, mut unresolved) = self.read_files(&context, &later, true)?;
+        unresolved.extend(later_unresolved);
+        if let Some(first) = unresolved.first().filter(|_| self.strict) {
+            return Err(first.clone().into());
+        }
+
+        let mut settings = Vec::new();
+        for (_, _, file) in files {
+            settings.extend(file);
+        }
+        settings.extend(later);
+
+        let mut npmrc = Npmrc::from_settings(settings, context)?;
+        npmrc.unresolved = unresolved;
+
+        match npmrc.invalid_values().first() {
+            Some(invalid) if self.strict => Err(invalid.clone().into()),
+            _ => Ok(npmrc),
+        }
+    }
+
+    // The `npm_config_*` and override layers, which npm reads before any
+    // file, and the references to unset variables in them.
+    fn env_and_overrides(&self) -> (Vec<Setting>, Vec<UnresolvedEnv>) {
+        let (mut settings, mut unresolved) = env_layer(&self.vars(), self.expand_env);
+        let mut overrides: Vec<Setting> = self
+            .overrides
+            .iter()
+            .map(|(key, value)| Setting::new(key.clone(), value.clone(), Source::new(Layer::Cli)))
+            .collect();
+        if self.expand_env {
+            unresolved.extend(expand_settings(&mut overrides, |name| self.var(name)));
+        }
+        settings.extend(overrides);
+        (settings, unresolved)
+    }
+
+    fn context(&self, project: Project) -> Result<Context, Error> {
+        Ok(Context {
+            home: self.home_dir(),
+            cwd: self.working_dir()?,
+            proxy_env: ProxyEnv::new(|name| self.var(name)),
+            project: Some(project),
+            prefix: self.global_prefix(),
+            fs: self.fs.clone(),
+        })
+    }
+
+    // The configured home directory, the one in the configured environment,
+    // or the current user's.
+    fn home_dir(&self) -> Option<PathBuf> {
+        if let Some(ref home) = self.home {
+            return Some(home.clone());
+        }
+        match self.env {
+            Some(ref env) => env
+                .get("HOME")
+                .or_else(|| env.get("USERPROFILE"))
+                .map(PathBuf::from),
+            None => dirs::home_dir(),
+        }
+    }
+
+    // The configured working directory, or the process' own.
+    fn working_dir(&self) -> Result<PathBuf, Error> {
+        match self.cwd {
+            Some(ref cwd) => Ok(cwd.clone()),
+            None => Ok(env::current_dir()?),
+        }
+    }
+
+    // Look up a variable in the configured environment.
+    fn var(&self, name: &str) -> Option<String> {
+        match self.env {
+            Some(ref env) => env.get(name).cloned(),
+            None => env::var(name).ok(),
+        }
+    }
+
+    // All variables in the configured environment.
+    fn vars(&self) -> HashMap<String, String> {
+        match self.env {
+            Some(ref env) => env.clone(),
+            None => env::vars().collect(),
+        }
+    }
+
+    // The prefix npm installs global packages into when `prefix` isn't set:
+    // `$PREFIX`, or the directory Node.js is installed in, under `$DESTDIR`
+    // if that's set.
+    fn global_prefix(&self) -> Option<PathBuf> {
+        if let Some(prefix) = self.var("PREFIX") {
+            return Some(resolve_path(&prefix, None, &self.working_dir().ok()?));
+        }
+
+        let node = if cfg!(windows) { "node.exe" } else { "node" };
+        let node = self.fs.canonicalize(&self.which(node)?).ok()?;
+        if cfg!(windows) {
+            return node.parent().map(Path::to_path_buf);
+        }
+
+        let prefix = node.parent()?.parent()?;
+        match self.var("DESTDIR") {
+            // Like `path.join`, nest the absolute prefix inside `$DESTDIR`.
+            Some(destdir) => {
+                let relative = prefix.strip_prefix("/").unwrap_or(prefix);
+                Some(PathBuf::from(destdir).join(relative))
+            }
+            None => Some(prefix.to_path_buf()),
+        }
+    }
+
+    // The root of the npm package itself, found through the `npm` executable.
+    fn npm_root(&self) -> Option<PathBuf> {
+        let npm = self.which(if cfg!(windows) { "npm.cmd" } else { "npm" })?;
+        if cfg!(windows) {
+            return npm.parent().map(|dir| dir.join("node_modules").join("npm"));
+        }
+
+        // `bin/npm` links to `lib/node_modules/npm/bin/npm-cli.js`.
+        let cli = self.fs.canonicalize(&npm).ok()?;
+        cli.parent()?.parent().map(Path::to_path_buf)
+    }
+
+    // Find an executable on `$PATH`.
+    fn which(&self, name: &str) -> Option<PathBuf> {
+        let path = self.var("PATH")?;
+        env::split_paths(&path)
+            .map(|dir| dir.join(name))
+            .find(|candidate| self.fs.is_file(candidate))
+    }
+}
+
+/// Read every configuration layer npm would, starting from the current directory.
+pub fn load() -> Result<Npmrc, Error> {
+    Loader::new().load()
+}
+
+// A config file: its layer, path and settings.
+type File = (Layer, PathBuf, Vec<Setting>);
+
+// The path `key` is set to in the layers read so far, resolved like any path
+// value.
+fn configured_path(
+    key: &str,
+    files: &[File],
+    later: &[Setting],
+    context: &Context,
+) -> Option<PathBuf> {
+    let setting = files
+        .iter()
+        .flat_map(|(_, _, settings)| settings)
+        .chain(later)
+        .filter(|setting| setting.key == key)
+        .max_by_key(|setting| setting.source.layer)?;
+    let definition = definition(key)?;
+    match coerce(definition, &setting.value, context) {
+        Ok(Some(path)) => Some(PathBuf::from(path)),
+        _ => None,
+    }
+}
+
+// Read a single ini file, treating a missing file as an empty layer.
+fn read_file(fs: &dyn FileSystem, layer: Layer, path: &Path) -> Result<Vec<Setting>, Error> {
+    match fs.read_to_string(path) {
+        Ok(contents) => parse_settings(layer, Some(path), &contents),
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(err) => Err(Error::io(path, err)),
+    }
+}
+
+// Parse the contents of a config file into settings attributed to `path`, or
+// to no file for config given as a string.
+pub(crate) fn parse_settings(
+    layer: Layer,
+    path: Option<&Path>,
+    contents: &str,
+) -> Result<Vec<Setting>, Error> {
+    let entries = ini::parse(contents).map_err(|err| Error::syntax(path, err))?;
+    Ok(entries
+        .into_iter()
+        .map(|entry| {
+            let source = Source::file(layer, path.map(Path::to_path_buf), entry.line, entry.text);
+            Setting::new(entry.key, entry.value, source)
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use MemoryFs;
+
+    // With `npm` and `node` in /usr/local/bin, the builtin config is
+    // /usr/local/npmrc and the global config /usr/local/etc/npmrc. The
+    // project is /repo.
+    fn fs() -> MemoryFs {
+        MemoryFs::new()
+            .file("/usr/local/bin/npm", "")
+            .file("/usr/local/bin/node", "")
+            .file("/repo/package.json", "{}")
+    }
+
+    fn loader(fs: MemoryFs, env: &[(&str, &str)]) -> Loader {
+        let mut vars = vec![("PATH", "/usr/local/bin"), ("HOME", "/home/me")];
+        vars.extend_from_slice(env);
+        Loader::new().fs(fs).cwd("/repo").env(vars)
+    }
+
+    fn files(loader: &Loader) -> Vec<(Layer, PathBuf)> {
+        loader.files().unwrap()
+    }
+
+    #[test]
+    fn finds_every_file() {
+        assert_eq!(
+            files(&loader(fs(), &[])),
+            [
+                (Layer::Builtin, PathBuf::from("/usr/local/npmrc")),
+                (Layer::Global, PathBuf::from("/usr/local/etc/npmrc")),
+                (Layer::User, PathBuf::from("/home/me/.npmrc")),
+                (Layer::Project, PathBuf::from("/repo/.npmrc")),
+            ]
+        );
+    }
+
+    #[test]
+    fn layers_take_precedence_in_order() {
+        let names = ["builtin", "global", "user", "project", "env", "cli"];
+        let paths = [
+            "/usr/local/npmrc",
+            "/usr/local/etc/npmrc",
+            "/home/me/.npmrc",
+            "/repo/.npmrc",
+        ];
+
+        for top in 0..names.len() {
+            let mut fs = fs();
+            for (layer, path) in paths.iter().enumerate().take(top + 1) {
+                fs.insert(*path, format!("tag={}\n", names[layer]));
+            }
+            let env: &[(&str, &str)] = if top >= 4 {
+                &[("npm_config_tag", "env")]
+            } else {
+                &[]
+            };
+            let mut loader = loader(fs, env);
+            if top == 5 {
+                loader = loader.set("tag", "cli");
+            }
+
+            let npmrc = loader.load().unwrap();
+            assert_eq!(npmrc.get("tag"), Some(names[top]));
+            let assignments = npmrc.explain("tag").assignments;
+            let layers: Vec<String> = assignments
+                .iter()
+                .map(|assignment| assignment.source.layer.to_string())
+                .collect();
+            assert_eq!(layers, &names[..=top]);
+            let active: Vec<bool> = assignments.iter().map(|a| a.active).collect();
+            let mut expected = vec![false; top];
+            expected.push(true);
+            assert_eq!(active, expected);
+        }
+    }
+
+    #[test]
+    fn lowercase_env_prefix_wins() {
+        let npmrc = loader(
+            fs(),
+            &[
+                ("NPM_CONFIG_TAG", "upper"),
+                ("npm_config_tag", "lower"),
+                ("Npm_Config_Tag", "mixed"),
+            ],
+        )
+        .load()
+        .unwrap();
+        assert_eq!(npmrc.get("tag"), Some("lower"));
+        assert_eq!(npmrc.source("tag").unwrap().layer, Layer::Env);
+    }
+
+    #[test]
+    fn env_keys_are_normalized_except_nerf_darts() {
+        let npmrc = loader(
+            fs(),
+            &[
+                ("NPM_CONFIG_SAVE_EXACT", "true"),
+                ("npm_config__auth", "dXNlcjpwYXNz"),
+                ("npm_config_//registry.example.com/:_authToken", "npm_abc"),
+            ],
+        )
+        .load()
+        .unwrap();
+        assert_eq!(
+            npmrc.keys(),
+            ["//registry.example.com/:_authToken", "_auth", "save-exact"]
+        );
+        assert_eq!(
+            npmrc.get("//registry.example.com/:_authToken"),
+            Some("npm_abc")
+        );
+        assert_eq!(npmrc.get("SAVE_EXACT"), Some("true"));
+        assert_eq!(npmrc.list("save_exact"), ["true"]);
+    }
+
+    #[test]
+    fn merge_env_matches_loading_with_the_env() {
+        let fs = fs()
+            .file("/home/me/.npmrc", "tag=${USER_TAG}\nsave=false\n")
+            .file("/repo/.npmrc", "registry=https://project.example/\n");
+        let before = [("npm_config_save", "true"), ("npm_config_tag", "old")];
+        let after = [
+            ("HOST", "registry.example.com"),
+            ("npm_config_registry", "https://${HOST}/"),
+            ("npm_config_preid", "${MISSING}"),
+        ];
+
+        let merged = loader(fs.clone(), &before)
+            .set("loglevel", "${LEVEL}")
+            .load()
+            .unwrap()
+            .merge_env(
+                after
+                    .iter()
+                    .cloned()
+                    .chain(vec![("PATH", "/usr/local/bin"), ("HOME", "/home/me")]),
+            )
+            .unwrap();
+        let loaded = loader(fs, &after)
+            .set("loglevel", "${LEVEL}")
+            .load()
+            .unwrap();
+
+        assert_eq!(
+            merged.get("registry"),
+            Some("https://registry.example.com/")
+        );
+        assert_eq!(merged.to_json_unredacted(), loaded.to_json_unredacted());
+        assert_eq!(merged.unresolved_env(), loaded.unresolved_env());
+        let names: Vec<&str> = merged
+            .unresolved_env()
+            .iter()
+            .map(|unresolved| unresolved.name.as_str())
+            .collect();
+        assert_eq!(names, ["USER_TAG", "MISSING", "LEVEL"]);
+        for key in loaded.keys() {
+            assert_eq!(merged.explain(key), loaded.explain(key));
+        }
+    }
+
+    // The value, `active` and `append` of every assignment of `key`.
+    fn assignments(npmrc: &Npmrc, key: &str) -> Vec<(String, bool, bool)> {
+        npmrc
+            .explain(key)
+            .assignments
+            .into_iter()
+            .map(|a| (a.value, a.active, a.append))
+            .collect()
+    }
+
+    #[test]
+    fn lists_append_within_a_file_and_are_replaced_by_later_layers() {
+        let fs = fs().file("/home/me/.npmrc", "omit[]=dev\nomit[]=peer\n");
+        let npmrc = loader(fs.clone(), &[]).load().unwrap();
+        assert_eq!(npmrc.list("omit"), ["dev", "peer"]);
+        assert_eq!(
+            assignments(&npmrc, "omit"),
+            [("dev".into(), true, true), ("peer".into(), true, true)]
+        );
+
+        let fs = fs.file("/repo/.npmrc", "omit[]=optional\n");
+        let npmrc = loader(fs.clone(), &[]).load().unwrap();
+        assert_eq!(npmrc.list("omit"), ["optional"]);
+        assert_eq!(
+            assignments(&npmrc, "omit"),
+            [
+                ("dev".into(), false, true),
+                ("peer".into(), false, true),
+                ("optional".into(), true, true),
+            ]
+        );
+        let explanation = npmrc.explain("omit");
+        assert_eq!(explanation.active().unwrap().value, "optional");
+        assert_eq!(
+            explanation.to_string(),
+            "; \"user\" config from /home/me/.npmrc:1\n\
+             omit[] = \"dev\" ; overridden by project\n\
+             ; \"user\" config from /home/me/.npmrc:2\n\
+             omit[] = \"peer\" ; overridden by project\n\
+             ; \"project\" config from /repo/.npmrc:1\n\
+             omit[] = \"optional\"\n"
+        );
+
+        let npmrc = loader(fs, &[]).set("omit", "peer").load().unwrap();
+        assert_eq!(npmrc.list("omit"), ["peer"]);
+        assert_eq!(
+            assignments(&npmrc, "omit").pop(),
+            Some(("peer".into(), true, false))
+        );
+        let active: Vec<bool> = assignments(&npmrc, "omit")
+            .into_iter()
+            .map(|(_, active, _)| active)
+            .collect();
+        assert_eq!(active, [false, false, false, true]);
+    }
+    #[test]
+    fn project_honors_the_env_and_overrides() {
+        let fs = MemoryFs::new()
+            .file("/repo/package.json", r#"{"workspaces": ["packages/*"]}"#)
+            .file("/repo/packages/a/package.json", "{}");
+        let root = |env: &[(&str, &str)], overrides: &[(&str, &str)]| {
+            let mut loader = Loader::new()
+                .fs(fs.clone())
+                .cwd("/repo/packages/a")
+                .env(env.to_vec());
+            for &(key, value) in overrides {
+                loader = loader.set(key, value);
+            }
+            loader.project().unwrap().root
+        };
+
+        assert_eq!(root(&[], &[]), PathBuf::from("/repo"));
+        let package = PathBuf::from("/repo/packages/a");
+        for &(key, value) in &[
+            ("global", "true"),
+            ("location", "global"),
+            ("workspaces", "false"),
+        ] {
+            let var = format!("npm_config_{}", key);
+            assert_eq!(root(&[(&var, value)], &[]), package, "{}", var);
+            assert_eq!(root(&[], &[(key, value)]), package, "{}", key);
+        }
+        assert_eq!(
+            root(&[("NPM_CONFIG_GLOBAL", "")], &[]),
+            PathBuf::from("/repo")
+        );
+        assert_eq!(
+            root(&[("npm_config_global", "true")], &[("global", "false")]),
+            PathBuf::from("/repo")
+        );
+
+        let other = PathBuf::from("/other");
+        assert_eq!(root(&[("npm_config_prefix", "/other")], &[]), other);
+        assert_eq!(
+            root(
+                &[("npm_config_prefix", "/ignored")],
+                &[("prefix", "/other")]
+            ),
+            other
+        );
+        assert_eq!(
+            root(
+                &[("HOME", "/home/me"), ("npm_config_prefix", "~/proj")],
+                &[]
+            ),
+            PathBuf::from("/home/me/proj")
+        );
+    }
+    #[test]
+    fn skips_the_project_file_when_it_is_the_userconfig() {
+        let fs = fs().file("/repo/.npmrc", "tag=project\n");
+        let loader = loader(fs, &[("npm_config_userconfig", "/repo/.npmrc")]);
+        assert_eq!(
+            files(&loader),
+            [
+                (Layer::Builtin, PathBuf::from("/usr/local/npmrc")),
+                (Layer::Global, PathBuf::from("/usr/local/etc/npmrc")),
+                (Layer::User, PathBuf::from("/repo/.npmrc")),
+            ]
+        );
+        let npmrc = loader.load().unwrap();
+        assert_eq!(npmrc.source("tag").unwrap().layer, Layer::User);
+        assert_eq!(npmrc.explain("tag").assignments.len(), 1);
+    }
+
+    #[test]
+    fn config_can_move_the_files_read_after_it() {
+        let user = |loader: &Loader| files(loader)[2].clone();
+        let global = |loader: &Loader| files(loader)[1].clone();
+
+        let moved = loader(fs(), &[("NPM_CONFIG_USERCONFIG", "~/config/npmrc")]);
+        assert_eq!(
+            user(&moved),
+            (Layer::User, PathBuf::from("/home/me/config/npmrc"))
+        );
+
+        // The project config can move the user config, and the user config
+        // the global config.
+        let chained = fs()
+            .file("/repo/.npmrc", "userconfig=/etc/npmrc-user\n")
+            .file("/etc/npmrc-user", "globalconfig=/etc/npmrc-global\n");
+        let moved = loader(chained, &[]);
+        assert_eq!(
+            files(&moved),
+            [
+                (Layer::Builtin, PathBuf::from("/usr/local/npmrc")),
+                (Layer::Global, PathBuf::from("/etc/npmrc-global")),
+                (Layer::User, PathBuf::from("/etc/npmrc-user")),
+                (Layer::Project, PathBuf::from("/repo/.npmrc")),
+            ]
+        );
+
+        let prefixed = fs().file("/home/me/.npmrc", "prefix=/opt/node\n");
+        assert_eq!(
+            global(&loader(prefixed.clone(), &[])),
+            (Layer::Global, PathBuf::from("/opt/node/etc/npmrc"))
+        );
+        // `globalconfig` wins over `prefix`, wherever each is set.
+        let moved = loader(prefixed, &[("npm_config_globalconfig", "/etc/npmrc")]);
+        assert_eq!(global(&moved), (Layer::Global, PathBuf::from("/etc/npmrc")));
+        let moved = loader(fs(), &[("PREFIX", "/opt/env")]).set("prefix", "/opt/cli");
+        assert_eq!(
+            global(&moved),
+            (Layer::Global, PathBuf::from("/opt/cli/etc/npmrc"))
+        );
+
+        // So can the builtin config.
+        let builtin = fs().file("/usr/local/npmrc", "globalconfig=/etc/builtin-global\n");
+        assert_eq!(
+            global(&loader(builtin, &[])),
+            (Layer::Global, PathBuf::from("/etc/builtin-global"))
+        );
+    }
+}
@@ -0,0 +1,331 @@
+extern crate npmrc;
+extern crate serde_json;
+
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::env;
+use std::error;
+use std::fs::OpenOptions;
+use std::path::PathBuf;
+use std::process::{self, Command as Process};
+
+use npmrc::{Document, Layer, Loader, Npmrc, DEFINITIONS};
+
+const USAGE: &str = "\
+Usage: npmrc <command> [options]
+
+Commands:
+  get <key>              Print the value of a key
+  set <key> <value>      Set a key, or add to a list with `key[]`
+  delete <key>           Remove every assignment of a key
+  list                   Print every configured key
+  edit                   Open a config file in $EDITOR
+
+Options:
+  --location <location>  Use the user, project or global config file
+  -g, --global           Same as --location global
+  --file <path>          Use this config file
+  --json                 Print `list` as JSON, like `npm config list --json`
+  -l, --long             Show where `list` values were set, and the defaults
+  --reveal               Print credentials with `get` instead of masking them
+  -h, --help             Print this message
+
+`get` and `list` show the merged config unless a location or file is given.
+`list` masks credentials like `_authToken`, or leaves them out of the merged
+JSON like npm does. `get` prints them as `(protected)` unless `--reveal` is
+given.
+`set`, `delete` and `edit` change the user config file by default.";
+
+type Result<T> = ::std::result::Result<T, Box<dyn error::Error>>;
+
+enum Command {
+    Get(String),
+    Set(String, String),
+    Delete(String),
+    List,
+    Edit,
+    Help,
+}
+
+#[derive(Default)]
+struct Options {
+    location: Option<Layer>,
+    file: Option<PathBuf>,
+    json: bool,
+    long: bool,
+    reveal: bool,
+}
+
+fn main() {
+    let (command, options) = match parse_args(env::args().skip(1)) {
+        Ok(args) => args,
+        Err(message) => {
+            eprintln!("npmrc: {}\n\n{}", message, USAGE);
+            process::exit(2);
+        }
+    };
+
+    let result = match command {
+        Command::Get(key) => get(&options, &key),
+        Command::Set(key, value) => set(&options, &key, &value),
+        Command::Delete(key) => delete(&options, &key),
+        Command::List => list(&options),
+        Command::Edit => edit(&options),
+        Command::Help => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+    };
+
+    if let Err(err) = result {
+        eprintln!("npmrc: {}", err);
+        process::exit(1);
+    }
+}
+
+fn parse_args<I: Iterator<Item = String>>(
+    mut args: I,
+) -> ::std::result::Result<(Command, Options), String> {
+    let mut options = Options::default();
+    let mut operands = Vec::new();
+
+    while let Some(arg) = args.next() {
+        let (flag, inline) = match arg.find('=') {
+            Some(eq) if arg.starts_with("--") => (&arg[..eq], Some(arg[eq + 1..].to_string())),
+            _ => (arg.as_str(), None),
+        };
+
+        match flag {
+            "--location" => {
+                let location = option_value(flag, inline, &mut args)?;
+                options.location = Some(match location.as_str() {
+                    "user" => Layer::User,
+                    "project" => Layer::Project,
+                    "global" => Layer::Global,
+                    _ => return Err(format!("unknown location `{}`", location)),
+                });
+            }
+            "-g" | "--global" => options.location = Some(Layer::Global),
+            "--file" => options.file = Some(option_value(flag, inline, &mut args)?.into()),
+            "--json" => options.json = true,
+            "-l" | "--long" => options.long = true,
+            "--reveal" => options.reveal = true,
+            "-h" | "--help" => return Ok((Command::Help, options)),
+            "--" => operands.extend(args.by_ref()),
+            _ if flag.starts_with('-') && flag.len() > 1 => {
+                return Err(format!("unknown option `{}`", flag))
+            }
+            _ => operands.push(arg.clone()),
+        }
+    }
+
+    let mut operands = operands.into_iter();
+    let name = operands.next();
+    let rest: Vec<String> = operands.collect();
+    let command = match (name.as_deref(), rest.as_slice()) {
+        (Some("get"), [key]) => Command::Get(key.clone()),
+        (Some("set"), [key, value]) => Command::Set(key.clone(), value.clone()),
+        (Some("delete"), [key]) => Command::Delete(key.clone()),
+        (Some("list"), []) | (Some("ls"), []) => Command::List,
+        (Some("edit"), []) => Command::Edit,
+        (Some("help"), []) => Command::Help,
+        (Some(name @ "get"), _) | (Some(name @ "delete"), _) => {
+            return Err(format!("`{}` takes a key", name))
+        }
+        (Some("set"), _) => return Err("`set` takes a key and a value".to_string()),
+        (Some(name @ "list"), _) | (Some(name @ "ls"), _) | (Some(name @ "edit"), _) => {
+            return Err(format!("`{}` takes no arguments", name))
+        }
+        (Some(name), _) => return Err(format!("unknown command `{}`", name)),
+        (None, _) => return Err("no command given".to_string()),
+    };
+
+    Ok((command, options))
+}
+
+// The value of an option, given either as `--option=value` or `--option value`.
+fn option_value<I: Iterator<Item = String>>(
+    flag: &str,
+    inline: Option<String>,
+    args: &mut I,
+) -> ::std::result::Result<String, String> {
+    inline
+        .or_else(|| args.next())
+        .ok_or_else(|| format!("`{}` needs a value", flag))
+}
+
+fn get(options: &Options, key: &str) -> Result<()> {
+    let values = match file(options)? {
+        Some(path) => Document::open(path)?.get_all(key),
+        None => npmrc::load()?.list(key),
+    };
+
+    if values.is_empty() {
+        println!("undefined");
+    }
+    for value in values {
+        if options.reveal {
+            println!("{}", value);
+        } else if npmrc::is_secret_key(key) {
+            // Like `npm config get`.
+            println!("(protected)");
+        } else {
+            println!("{}", npmrc::redact(key, &value));
+        }
+    }
+    Ok(())
+}
+
+fn set(options: &Options, key: &str, value: &str) -> Result<()> {
+    let path = file_or_user(options)?;
+    let mut document = Document::open(&path)?;
+    match key.strip_suffix("[]") {
+        Some(key) => document.push(key, value),
+        None => document.set(key, value),
+    }
+    document.save(&path)?;
+    Ok(())
+}
+
+fn delete(options: &Options, key: &str) -> Result<()> {
+    let path = file_or_user(options)?;
+    let mut document = Document::open(&path)?;
+    if document.delete(key) {
+        document.save(&path)?;
+    }
+    Ok(())
+}
+
+fn list(options: &Options) -> Result<()> {
+    let mut values = BTreeMap::new();
+    let mut npmrc = None;
+
+    match file(options)? {
+        Some(path) => {
+            let document = Document::open(path)?;
+            for key in document.keys() {
+                let items = redacted(&key, document.get_all(&key));
+                values.insert(key, items);
+            }
+        }
+        None => {
+            let config = npmrc::load()?;
+            // The merged config prints like `npm config list --json`.
+            if options.json {
+                println!("{}", serde_json::to_string_pretty(&config)?);
+                return Ok(());
+            }
+            for key in config.keys() {
+                values.insert(key.to_string(), redacted(key, config.list(key)));
+            }
+            npmrc = Some(config);
+        }
+    }
+
+    let defaults: Vec<_> = DEFINITIONS
+        .iter()
+        .filter(|definition| !values.contains_key(definition.key))
+        .filter_map(|definition| definition.default.map(|default| (definition.key, default)))
+        .collect();
+
+    if options.json {
+        let mut json: BTreeMap<&str, Value> = values
+            .iter()
+            .map(|(key, items)| (key.as_str(), to_json(items)))
+            .collect();
+        if options.long {
+            for &(key, default) in &defaults {
+                json.insert(key, Value::from(default));
+            }
+        }
+        println!("{}", serde_json::to_string_pretty(&json)?);
+        return Ok(());
+    }
+
+    match npmrc {
+        Some(ref npmrc) if options.long => print_explained(npmrc),
+        _ => {
+            for (key, items) in &values {
+                println!("{} = {}", key, to_json(items));
+            }
+        }
+    }
+
+    if options.long {
+        println!("\n; default values");
+        for (key, default) in defaults {
+            println!("{} = {}", key, Value::from(default));
+        }
+    }
+    Ok(())
+}
+
+fn print_explained(npmrc: &Npmrc) {
+    for key in npmrc.keys() {
+        print!("{}", npmrc.explain(key));
+    }
+}
+
+fn edit(options: &Options) -> Result<()> {
+    let path = file_or_user(options)?;
+    // Make sure there is a file to open.
+    OpenOptions::new().create(true).append(true).open(&path)?;
+
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| if cfg!(windows) { "notepad" } else { "vi" }.to_string());
+    let mut words = editor.split_whitespace();
+    let program = words.next().ok_or("$EDITOR is empty")?;
+
+    let status = Process::new(program).args(words).arg(&path).status()?;
+    if !status.success() {
+        return Err(format!("{} exited with {}", program, status).into());
+    }
+    Ok(())
+}
+
+// The file chosen with `--file` or `--location`, if any.
+fn file(options: &Options) -> Result<Option<PathBuf>> {
+    if let Some(ref file) = options.file {
+        return Ok(Some(file.clone()));
+    }
+    match options.location {
+        Some(layer) => Ok(Some(location_file(layer)?)),
+        None => Ok(None),
+    }
+}
+
+// The file to change, defaulting to the user config.
+fn file_or_user(options: &Options) -> Result<PathBuf> {
+    match file(options)? {
+        Some(path) => Ok(path),
+        None => location_file(Layer::User),
+    }
+}
+
+// Locating a file doesn't fail on unset variables or on broken config files
+// other than the one being changed, so those can still be fixed with `set`.
+fn location_file(layer: Layer) -> Result<PathBuf> {
+    Loader::new()
+        .files()?
+        .into_iter()
+        .find(|&(file_layer, _)| file_layer == layer)
+        .map(|(_, path)| path)
+        .ok_or_else(|| format!("couldn't find the {} config file", layer).into())
+}
+
+// Mask credentials, like `npm config list` does.
+fn redacted(key: &str, items: Vec<String>) -> Vec<String> {
+    items
+        .iter()
+        .map(|item| npmrc::redact(key, item).into_owned())
+        .collect()
+}
+
+// A single value as a string, and a list as an array.
+fn to_json(items: &[String]) -> Value {
+    match items {
+        [item] => Value::from(item.as_str()),
+        _ => Value::from(items.to_vec()),
+    }
+}
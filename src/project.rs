@@ -0,0 +1,312 @@
+//! Finding the project a directory belongs to.
+
+use serde_json::{self, Value};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use filesystem::{FileSystem, RealFs};
+
+/// Finds the project a directory belongs to, and with it the project
+/// `.npmrc` npm reads.
+///
+/// Like npm 7 and later, walking up from the start directory, the nearest
+/// directory with a `package.json` or `node_modules` is the project. If a
+/// directory further up has a `package.json` whose `workspaces` include that
+/// project, the workspace root is the project instead, and the workspace's
+/// own `.npmrc` is ignored.
+///
+/// ```
+/// use std::path::Path;
+///
+/// let fs = npmrc::MemoryFs::new()
+///     .file("/repo/package.json", r#"{"workspaces": ["packages/*"]}"#)
+///     .file("/repo/packages/app/package.json", "{}");
+/// let project = npmrc::ProjectLocator::new("/repo/packages/app")
+///     .fs(fs)
+///     .locate();
+/// assert_eq!(project.root, Path::new("/repo"));
+/// println!("{}", project);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ProjectLocator {
+    start: PathBuf,
+    prefix: Option<PathBuf>,
+    workspaces: bool,
+    fs: Arc<dyn FileSystem>,
+}
+
+/// The project found by `ProjectLocator::locate`.
+///
+/// Displays as an explanation of why its `.npmrc` was chosen:
+///
+/// ```text
+/// using /repo/.npmrc: /repo is the workspace root of /repo/packages/app,
+/// matched by `packages/*`; /repo/packages/app/.npmrc is ignored
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Project {
+    /// The project's root directory, npm's local prefix.
+    pub root: PathBuf,
+
+    /// Why `root` was chosen.
+    pub reason: ProjectReason,
+
+    /// The workspace the start directory is in, when `root` is a workspace
+    /// root.
+    pub workspace: Option<PathBuf>,
+
+    package_json: Option<PathBuf>,
+    ignored_npmrc: Option<PathBuf>,
+}
+
+/// Why a directory was chosen as the project root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProjectReason {
+    /// It's the nearest directory with a `package.json`.
+    PackageJson,
+
+    /// It's the nearest directory with a `node_modules`, and has no
+    /// `package.json`.
+    NodeModules,
+
+    /// Its `package.json` lists the nearest project as a workspace.
+    WorkspaceRoot {
+        /// The `workspaces` pattern that matched.
+        pattern: String,
+    },
+
+    /// It was given as the `prefix`, like `npm --prefix <dir>`.
+    Prefix,
+
+    /// No directory above the start has a `package.json` or `node_modules`,
+    /// so the start directory is used.
+    StartDirectory,
+}
+
+impl ProjectLocator {
+    /// Create a locator that walks up from `start`.
+    pub fn new<P: Into<PathBuf>>(start: P) -> Self {
+        ProjectLocator {
+            start: start.into(),
+            prefix: None,
+            workspaces: true,
+            fs: Arc::new(RealFs),
+        }
+    }
+
+    /// Look for files in `fs` instead of the real filesystem.
+    pub fn fs<F: FileSystem + 'static>(mut self, fs: F) -> Self {
+        self.fs = Arc::new(fs);
+        self
+    }
+
+    /// Use `prefix` as the project root instead of looking for one, like npm
+    /// does for a `--prefix` flag.
+    pub fn prefix<P: Into<PathBuf>>(mut self, prefix: P) -> Self {
+        self.prefix = Some(prefix.into());
+        self
+    }
+
+    /// Whether to look for a workspace root above the nearest project.
+    ///
+    /// Enabled by default. npm turns it off for `--workspaces=false` and for
+    /// global commands.
+    pub fn workspaces(mut self, enabled: bool) -> Self {
+        self.workspaces = enabled;
+        self
+    }
+
+    /// Find the project: the prefix if one was given, otherwise by walking up
+    /// from the start directory.
+    pub fn locate(&self) -> Project {
+        if let Some(ref prefix) = self.prefix {
+            return self.project(prefix, ProjectReason::Prefix, None);
+        }
+
+        // The filesystem root is never a project.
+        let mut dirs = self.start.ancestors().filter(|dir| dir.parent().is_some());
+
+        let (nearest, reason) = match dirs.by_ref().find_map(|dir| {
+            if self.fs.is_file(&dir.join("package.json")) {
+                Some((dir, ProjectReason::PackageJson))
+            } else if self.fs.is_dir(&dir.join("node_modules")) {
+                Some((dir, ProjectReason::NodeModules))
+            } else {
+                None
+            }
+        }) {
+            Some(found) => found,
+            None => return self.project(&self.start, ProjectReason::StartDirectory, None),
+        };
+
+        // Only a package can be a workspace.
+        if self.workspaces && reason == ProjectReason::PackageJson {
+            for dir in dirs {
+                if let Some(pattern) = workspace_pattern(&*self.fs, dir, nearest) {
+                    let reason = ProjectReason::WorkspaceRoot { pattern };
+                    return self.project(dir, reason, Some(nearest));
+                }
+            }
+        }
+
+        self.project(nearest, reason, None)
+    }
+
+    fn project(&self, root: &Path, reason: ProjectReason, workspace: Option<&Path>) -> Project {
+        let exists = |path: PathBuf| Some(path).filter(|path| self.fs.is_file(path));
+        Project {
+            root: root.to_path_buf(),
+            reason,
+            workspace: workspace.map(Path::to_path_buf),
+            package_json: exists(root.join("package.json")),
+            ignored_npmrc: workspace.and_then(|workspace| exists(workspace.join(".npmrc"))),
+        }
+    }
+}
+
+impl Project {
+    /// The project `.npmrc` npm reads, whether or not it exists.
+    pub fn npmrc(&self) -> PathBuf {
+        self.root.join(".npmrc")
+    }
+
+    /// The project's `package.json`, if it has one.
+    pub fn package_json(&self) -> Option<PathBuf> {
+        self.package_json.clone()
+    }
+
+    /// The workspace's own `.npmrc`, if there is one. npm ignores it in
+    /// favor of the workspace root's.
+    pub fn ignored_npmrc(&self) -> Option<PathBuf> {
+        self.ignored_npmrc.clone()
+    }
+}
+
+impl fmt::Display for Project {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let root = self.root.display();
+        write!(f, "using {}: ", self.npmrc().display())?;
+        match self.reason {
+            ProjectReason::PackageJson => {
+                write!(f, "{} is the nearest directory with a package.json", root)
+            }
+            ProjectReason::NodeModules => {
+                write!(f, "{} is the nearest directory with node_modules", root)
+            }
+            ProjectReason::WorkspaceRoot { ref pattern } => {
+                if let Some(ref workspace) = self.workspace {
+                    write!(
+                        f,
+                        "{} is the workspace root of {}, matched by `{}`",
+                        root,
+                        workspace.display(),
+                        pattern
+                    )?;
+                }
+                match self.ignored_npmrc() {
+                    Some(ignored) => write!(f, "; {} is ignored", ignored.display()),
+                    None => Ok(()),
+                }
+            }
+            ProjectReason::Prefix => write!(f, "{} is the configured prefix", root),
+            ProjectReason::StartDirectory => {
+                write!(f, "no package.json or node_modules found above {}", root)
+            }
+        }
+    }
+}
+
+// The pattern in `root`'s `workspaces` that includes `package`, if any.
+//
+// `workspaces` is either a list of patterns, or an object with a `packages`
+// list. Patterns starting with `!` exclude what they match.
+fn workspace_pattern(fs: &dyn FileSystem, root: &Path, package: &Path) -> Option<String> {
+    let contents = fs.read_to_string(&root.join("package.json")).ok()?;
+    let manifest: Value = serde_json::from_str(&contents).ok()?;
+    let workspaces = match manifest.get("workspaces")? {
+        Value::Object(object) => object.get("packages")?,
+        workspaces => workspaces,
+    };
+    let patterns: Vec<&str> = workspaces
+        .as_array()?
+        .iter()
+        .filter_map(Value::as_str)
+        .collect();
+
+    let relative = package.strip_prefix(root).ok()?;
+    let segments: Vec<String> = relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+    let matches = |pattern: &str| {
+        let pattern: Vec<&str> = pattern
+            .split('/')
+            .filter(|segment| !segment.is_empty() && *segment != ".")
+            .collect();
+        glob(&pattern, &segments)
+    };
+
+    let excluded = patterns
+        .iter()
+        .filter_map(|pattern| pattern.strip_prefix('!'))
+        .any(&matches);
+    if excluded {
+        return None;
+    }
+    patterns
+        .iter()
+        .find(|pattern| !pattern.starts_with('!') && matches(pattern))
+        .map(|pattern| pattern.to_string())
+}
+
+// Match path segments against pattern segments, where `**` matches any
+// number of segments.
+fn glob(pattern: &[&str], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => (0..=path.len())
+            .any(|skip| glob(rest, &path[skip..]) && !path[..skip].contains(&"node_modules")),
+        Some((segment, rest)) => match path.split_first() {
+            Some((name, path)) => segment_matches(segment, name) && glob(rest, path),
+            None => false,
+        },
+    }
+}
+
+// Match a single segment, where `*` matches any run of characters and `?`
+// any one character. Like npm's globbing, wildcards don't match a leading
+// `.`.
+fn segment_matches(pattern: &str, name: &str) -> bool {
+    if name.starts_with('.') && !pattern.starts_with('.') {
+        return false;
+    }
+
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    // Classic wildcard matching, backtracking to the last `*`.
+    let (mut p, mut n) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((star_p, star_n)) => {
+                    p = star_p + 1;
+                    n = star_n + 1;
+                    star = Some((star_p, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
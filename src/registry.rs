@@ -0,0 +1,145 @@
+//! URLs of a registry's endpoints.
+
+use std::fmt;
+use url::Url;
+
+use {Npmrc, PackageSpec};
+
+/// The `Accept` header that asks for abbreviated metadata, which only holds
+/// what's needed to install a package.
+pub const ABBREVIATED_ACCEPT: &str =
+    "application/vnd.npm.install-v1+json; q=1.0, application/json; q=0.8, */*";
+
+/// A registry, with its URL normalized so endpoints can be appended to it.
+///
+/// Registries mounted under a path, like Artifactory's
+/// `https://example.com/api/npm/npm-virtual`, keep that path whether or not
+/// the configured URL ends with a slash.
+///
+/// ```
+/// let registry = npmrc::Registry::new("https://registry.npmjs.org").unwrap();
+/// assert_eq!(
+///     registry.tarball("@myorg/pkg", "1.0.0"),
+///     "https://registry.npmjs.org/@myorg/pkg/-/pkg-1.0.0.tgz",
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Registry {
+    url: Url,
+}
+
+impl Registry {
+    /// Normalize a registry URL. Returns `None` unless it's an absolute
+    /// `http` or `https` URL.
+    pub fn new(url: &str) -> Option<Self> {
+        let mut url = Url::parse(url.trim()).ok()?;
+        if !(url.scheme() == "http" || url.scheme() == "https") || !url.has_host() {
+            return None;
+        }
+        url.set_query(None);
+        url.set_fragment(None);
+        if !url.path().ends_with('/') {
+            let path = format!("{}/", url.path());
+            url.set_path(&path);
+        }
+        Some(Registry { url })
+    }
+
+    /// The normalized URL, always ending with `/`.
+    pub fn url(&self) -> &str {
+        self.url.as_str()
+    }
+
+    /// The full metadata document of a package.
+    pub fn packument(&self, name: &str) -> String {
+        self.endpoint(&escape_name(name))
+    }
+
+    /// The abbreviated metadata document of a package.
+    ///
+    /// This is the packument URL; the registry returns the abbreviated form
+    /// when asked for it with `ABBREVIATED_ACCEPT`.
+    pub fn abbreviated(&self, name: &str) -> String {
+        self.packument(name)
+    }
+
+    /// The metadata of a single version, or of the version a dist-tag points
+    /// at.
+    pub fn version(&self, name: &str, version: &str) -> String {
+        self.endpoint(&format!("{}/{}", escape_name(name), encode(version)))
+    }
+
+    /// All dist-tags of a package.
+    pub fn dist_tags(&self, name: &str) -> String {
+        self.endpoint(&format!("-/package/{}/dist-tags", escape_name(name)))
+    }
+
+    /// A single dist-tag of a package, for setting or removing it.
+    pub fn dist_tag(&self, name: &str, tag: &str) -> String {
+        format!("{}/{}", self.dist_tags(name), encode(tag))
+    }
+
+    /// A search for packages matching `text`.
+    pub fn search(&self, text: &str) -> String {
+        let mut url = self.url.join("-/v1/search").expect("search path is valid");
+        url.query_pairs_mut().append_pair("text", text);
+        url.into()
+    }
+
+    /// The endpoint that reports who the credentials belong to.
+    pub fn whoami(&self) -> String {
+        self.endpoint("-/whoami")
+    }
+
+    /// The endpoint that checks the registry is reachable.
+    pub fn ping(&self) -> String {
+        self.endpoint("-/ping?write=true")
+    }
+
+    /// The tarball of a published version, following npm's naming of
+    /// `<name>/-/<unscoped name>-<version>.tgz`.
+    pub fn tarball(&self, name: &str, version: &str) -> String {
+        let unscoped = name.rsplit('/').next().unwrap_or(name);
+        self.endpoint(&format!("{}/-/{}-{}.tgz", name, unscoped, encode(version)))
+    }
+
+    fn endpoint(&self, path: &str) -> String {
+        format!("{}{}", self.url, path)
+    }
+}
+
+impl fmt::Display for Registry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.url())
+    }
+}
+
+impl Npmrc {
+    /// The registry a registry-based spec is fetched from: its scope's
+    /// registry if one is configured, otherwise the default registry.
+    ///
+    /// Returns `None` for specs that don't come from a registry, or if the
+    /// configured URL isn't a valid registry URL.
+    pub fn registry_for(&self, spec: &PackageSpec) -> Option<Registry> {
+        Registry::new(self.registry_for_name(spec.registry_name()?))
+    }
+}
+
+// Scoped names keep their `@` but escape the `/`, like `@scope%2fname`.
+fn escape_name(name: &str) -> String {
+    name.replace('/', "%2f")
+}
+
+// Percent-encode everything but unreserved characters.
+fn encode(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                out.push(byte as char)
+            }
+            _ => out.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    out
+}
@@ -0,0 +1,274 @@
+//! Registries configured per package scope.
+
+use source::Assignment;
+use {Layer, Npmrc, Source};
+
+/// A scope whose packages come from their own registry, configured with
+/// `@scope:registry=<url>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scope {
+    /// The scope's name, lowercased and without its leading `@`.
+    pub name: String,
+
+    /// The registry the scope's packages are fetched from.
+    pub registry_url: String,
+}
+
+impl Scope {
+    /// Create a scope mapping. `name` may be given with or without its `@`,
+    /// in any case.
+    pub fn new(name: &str, registry_url: &str) -> Self {
+        Scope {
+            name: normalize(name),
+            registry_url: registry_url.to_string(),
+        }
+    }
+
+    /// The config key that maps the scope, e.g. `@myorg:registry`.
+    pub fn key(&self) -> String {
+        format!("@{}:registry", self.name)
+    }
+
+    /// Whether `package` is a package in this scope.
+    pub fn contains(&self, package: &str) -> bool {
+        match package
+            .strip_prefix('@')
+            .and_then(|rest| rest.split_once('/'))
+        {
+            Some((scope, _)) => scope.eq_ignore_ascii_case(&self.name),
+            None => false,
+        }
+    }
+}
+
+/// Turn `@MyOrg`, `myorg` or ` @myorg ` into `myorg`.
+pub(crate) fn normalize(scope: &str) -> String {
+    scope.trim().trim_start_matches('@').to_lowercase()
+}
+
+/// The normalized scope of an `@scope:registry` key, or `None` for any other
+/// key.
+pub(crate) fn registry_key_scope(key: &str) -> Option<String> {
+    let scope = key.strip_prefix('@')?.strip_suffix(":registry")?;
+    if scope.is_empty() || scope.contains([':', '/']) {
+        return None;
+    }
+    Some(normalize(scope))
+}
+
+impl Npmrc {
+    /// Every scope mapped to a registry, sorted by name.
+    pub fn scopes(&self) -> &[Scope] {
+        &self.scopes
+    }
+
+    /// The registry configured for `scope`, given with or without its `@`.
+    pub fn scope_registry(&self, scope: &str) -> Option<&str> {
+        let name = normalize(scope);
+        self.scopes
+            .iter()
+            .find(|scope| scope.name == name)
+            .map(|scope| scope.registry_url.as_str())
+    }
+
+    /// Point `scope` at `registry`, replacing any registry configured for it
+    /// in any layer.
+    ///
+    /// This only changes the loaded config. Use `Document::set_scope_registry`
+    /// to change a file.
+    pub fn set_scope_registry(&mut self, scope: &str, registry: &str) {
+        self.remove_scope_registry(scope);
+
+        let scope = Scope::new(scope, registry);
+        let key = scope.key();
+        self.other.insert(key.clone(), registry.to_string());
+        self.sources.insert(
+            key.clone(),
+            vec![Assignment {
+                value: registry.to_string(),
+                source: Source::new(Layer::Cli),
+                active: true,
+                append: false,
+            }],
+        );
+        self.lists.insert(key, vec![registry.to_string()]);
+        self.collect_scopes();
+    }
+
+    /// Remove the registry configured for `scope` in every layer, returning
+    /// whether there was one.
+    pub fn remove_scope_registry(&mut self, scope: &str) -> bool {
+        let name = normalize(scope);
+        let keys: Vec<String> = self
+            .sources
+            .keys()
+            .filter(|key| registry_key_scope(key).as_ref() == Some(&name))
+            .cloned()
+            .collect();
+
+        for key in &keys {
+            self.other.remove(key);
+            self.sources.remove(key);
+            self.lists.remove(key);
+        }
+        self.collect_scopes();
+        !keys.is_empty()
+    }
+
+    // Turn `@scope:registry` entries into `Scope`s. When a scope is mapped
+    // under differently cased keys, the one set with the highest precedence
+    // wins.
+    pub(crate) fn collect_scopes(&mut self) {
+        let mut scopes: Vec<(Scope, &Source)> = Vec::new();
+
+        for (key, assignments) in &self.sources {
+            let name = match registry_key_scope(key) {
+                Some(name) => name,
+                None => continue,
+            };
+            let assignment = match assignments
+                .iter()
+                .rev()
+                .find(|assignment| assignment.active)
+            {
+                Some(assignment) => assignment,
+                None => continue,
+            };
+            let scope = Scope {
+                name,
+                registry_url: assignment.value.clone(),
+            };
+            let source = &assignment.source;
+
+            match scopes
+                .iter()
+                .position(|(existing, _)| existing.name == scope.name)
+            {
+                Some(index) if precedes(scopes[index].1, source) => scopes[index] = (scope, source),
+                Some(_) => {}
+                None => scopes.push((scope, source)),
+            }
+        }
+
+        let mut scopes: Vec<Scope> = scopes.into_iter().map(|(scope, _)| scope).collect();
+        scopes.sort_by(|a, b| a.name.cmp(&b.name));
+        self.scopes = scopes;
+    }
+}
+
+// Whether a value set at `a` is overridden by one set at `b`.
+fn precedes(a: &Source, b: &Source) -> bool {
+    (a.layer, a.line) < (b.layer, b.line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {Loader, MemoryFs};
+
+    // Load a user and a project config.
+    fn load(user: &str, project: &str) -> Npmrc {
+        let fs = MemoryFs::new()
+            .file("/home/me/.npmrc", user)
+            .file("/repo/package.json", "{}")
+            .file("/repo/.npmrc", project);
+        Loader::new()
+            .fs(fs)
+            .cwd("/repo")
+            .env(vec![("HOME", "/home/me")])
+            .load()
+            .unwrap()
+    }
+
+    #[test]
+    fn normalizes_scope_names() {
+        for name in &["myorg", "@myorg", "@MyOrg", " @myorg ", "@@myorg"] {
+            assert_eq!(normalize(name), "myorg", "{:?}", name);
+        }
+        assert_eq!(
+            Scope::new("@MyOrg", "https://r.example/").key(),
+            "@myorg:registry"
+        );
+    }
+
+    #[test]
+    fn finds_the_scope_of_registry_keys() {
+        let cases = [
+            ("@myorg:registry", Some("myorg")),
+            ("@MyOrg:registry", Some("myorg")),
+            ("@@myorg:registry", Some("myorg")),
+            ("@:registry", None),
+            ("@my/org:registry", None),
+            ("@myorg:_authToken", None),
+            ("myorg:registry", None),
+            ("registry", None),
+            ("//r.example/:registry", None),
+        ];
+        for &(key, expected) in &cases {
+            assert_eq!(registry_key_scope(key).as_deref(), expected, "{}", key);
+        }
+    }
+
+    #[test]
+    fn highest_precedence_key_wins_across_casings() {
+        let npmrc = load(
+            "@MyOrg:registry=https://first.example/\n@myorg:registry=https://second.example/\n",
+            "",
+        );
+        assert_eq!(
+            npmrc.scopes(),
+            [Scope::new("myorg", "https://second.example/")]
+        );
+
+        // The project config wins over a later line of the user config.
+        let npmrc = load(
+            "\n\n@myorg:registry=https://user.example/\n",
+            "@MYORG:registry=https://project.example/\n",
+        );
+        assert_eq!(
+            npmrc.scope_registry("@MyOrg"),
+            Some("https://project.example/")
+        );
+
+        let npmrc = load(
+            "@MYORG:registry=https://user.example/\n",
+            "\n\n@myorg:registry=https://project.example/\n",
+        );
+        assert_eq!(
+            npmrc.scope_registry("myorg"),
+            Some("https://project.example/")
+        );
+        assert!(npmrc.scopes()[0].contains("@MyOrg/pkg"));
+    }
+
+    #[test]
+    fn setting_and_removing_a_scope_covers_every_layer() {
+        let user =
+            "@MyOrg:registry=https://user.example/\n@other:registry=https://other.example/\n";
+        let project = "@myorg:registry=https://project.example/\n";
+
+        let mut npmrc = load(user, project);
+        npmrc.set_scope_registry("@MYORG", "https://new.example/");
+        assert_eq!(
+            npmrc.scopes(),
+            [
+                Scope::new("myorg", "https://new.example/"),
+                Scope::new("other", "https://other.example/"),
+            ]
+        );
+        assert_eq!(npmrc.keys(), ["@myorg:registry", "@other:registry"]);
+        let explanation = npmrc.explain("@myorg:registry");
+        assert_eq!(explanation.assignments.len(), 1);
+        assert_eq!(explanation.active().unwrap().source.layer, Layer::Cli);
+
+        let mut npmrc = load(user, project);
+        assert!(npmrc.remove_scope_registry("myorg"));
+        assert_eq!(
+            npmrc.scopes(),
+            [Scope::new("other", "https://other.example/")]
+        );
+        assert_eq!(npmrc.keys(), ["@other:registry"]);
+        assert_eq!(npmrc.get("@MyOrg:registry"), None);
+        assert!(!npmrc.remove_scope_registry("@myorg"));
+    }
+}
@@ -0,0 +1,164 @@
+//! Where resolved config values came from.
+
+use std::fmt;
+use std::path::PathBuf;
+
+use secret::{redact, redact_line};
+use Layer;
+
+/// The origin of a single config value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Source {
+    /// The layer the value was set in.
+    pub layer: Layer,
+
+    /// The file the value was read from, if it came from a file rather than
+    /// a string.
+    pub path: Option<PathBuf>,
+
+    /// The one-based line the value was set on, if it came from a file or
+    /// string.
+    pub line: Option<usize>,
+
+    // The text of that line, for diagnostics, with any credential masked.
+    pub(crate) text: Option<String>,
+}
+
+impl Source {
+    pub(crate) fn new(layer: Layer) -> Self {
+        Source {
+            layer,
+            path: None,
+            line: None,
+            text: None,
+        }
+    }
+
+    pub(crate) fn file(layer: Layer, path: Option<PathBuf>, line: usize, text: String) -> Self {
+        Source {
+            layer,
+            path,
+            line: Some(line),
+            text: Some(redact_line(&text).into_owned()),
+        }
+    }
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\"{}\" config", self.layer)?;
+        match (&self.path, self.line) {
+            (Some(path), Some(line)) => write!(f, " from {}:{}", path.display(), line),
+            (Some(path), None) => write!(f, " from {}", path.display()),
+            (None, Some(line)) => write!(f, ", line {}", line),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+/// A value assigned to a key, together with where it was assigned.
+///
+/// The value is kept as written, credentials included. `Explanation`, which
+/// knows the key, masks them in its `Display` and `Debug` output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Assignment {
+    /// The raw value as written in its source.
+    pub value: String,
+
+    /// Where the value was set.
+    pub source: Source,
+
+    /// Whether this is the value that ended up in the resolved config.
+    ///
+    /// Every item of a list built up with `key[]=` is active.
+    pub active: bool,
+
+    /// Whether the value was added to a list with `key[]=value`.
+    pub append: bool,
+}
+
+/// Every assignment of a key across all layers, returned by `Npmrc::explain`.
+///
+/// Displays like `npm config ls -l`, with credentials masked as `***` there
+/// and in `Debug` output:
+///
+/// ```text
+/// ; "user" config from /home/me/.npmrc:3
+/// registry = "https://registry.npmjs.org/" ; overridden by project
+/// ; "project" config from /home/me/app/.npmrc:1
+/// registry = "https://npm.example.com/"
+/// ```
+#[derive(Clone, PartialEq, Eq)]
+pub struct Explanation {
+    /// The key being explained.
+    pub key: String,
+
+    /// Every assignment, from lowest to highest precedence.
+    pub assignments: Vec<Assignment>,
+}
+
+impl Explanation {
+    /// The assignment that won, if the key was set at all.
+    pub fn active(&self) -> Option<&Assignment> {
+        self.assignments.iter().find(|assignment| assignment.active)
+    }
+}
+
+impl fmt::Debug for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let assignments: Vec<Assignment> = self
+            .assignments
+            .iter()
+            .map(|assignment| Assignment {
+                value: redact(&self.key, &assignment.value).into_owned(),
+                ..assignment.clone()
+            })
+            .collect();
+        f.debug_struct("Explanation")
+            .field("key", &self.key)
+            .field("assignments", &assignments)
+            .finish()
+    }
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let winner = self.active().map(|assignment| assignment.source.layer);
+
+        for assignment in &self.assignments {
+            writeln!(f, "; {}", assignment.source)?;
+            let brackets = if assignment.append { "[]" } else { "" };
+            let value = redact(&self.key, &assignment.value);
+            write!(f, "{}{} = {:?}", self.key, brackets, value)?;
+            match winner {
+                Some(layer) if !assignment.active => writeln!(f, " ; overridden by {}", layer)?,
+                _ => writeln!(f)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use Npmrc;
+
+    #[test]
+    fn explanations_mask_credentials() {
+        let npmrc: Npmrc = "//r.example/:_authToken=npm_secret\n\
+                            https-proxy=user:s3cret@proxy.example.com:8080\n"
+            .parse()
+            .unwrap();
+        for key in npmrc.keys() {
+            let explanation = npmrc.explain(key);
+            for output in &[format!("{:?}", explanation), explanation.to_string()] {
+                assert!(!output.contains("npm_secret"), "{}", output);
+                assert!(!output.contains("s3cret"), "{}", output);
+            }
+        }
+        assert_eq!(
+            npmrc.explain("//r.example/:_authToken").assignments[0].value,
+            "npm_secret"
+        );
+    }
+}
@@ -0,0 +1,650 @@
+//! Parsing of package specifiers, like npm's `npm-package-arg`.
+
+use std::error;
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use credentials::RegistryCredentials;
+use Npmrc;
+
+/// Prefixes of hosted git shorthands like `github:user/repo`.
+const HOSTED_GIT: &[&str] = &["bitbucket:", "gist:", "github:", "gitlab:"];
+
+/// A parsed package specifier, as accepted by `npm install`.
+///
+/// ```
+/// # fn main() -> Result<(), npmrc::SpecError> {
+/// let spec: npmrc::PackageSpec = "@myorg/pkg@^1.2".parse()?;
+/// assert_eq!(spec.name.as_ref().unwrap(), "@myorg/pkg");
+/// assert_eq!(spec.kind, npmrc::SpecKind::Range("^1.2".into()));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackageSpec {
+    /// The package name, if the specifier has one.
+    pub name: Option<String>,
+
+    /// The specifier as given.
+    pub raw: String,
+
+    /// What the specifier points at.
+    pub kind: SpecKind,
+}
+
+/// What a `PackageSpec` points at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SpecKind {
+    /// An exact version from the registry, like `1.2.3`.
+    Version(String),
+
+    /// A semver range from the registry, like `^1.2` or `*`.
+    Range(String),
+
+    /// A dist-tag from the registry, like `latest`.
+    Tag(String),
+
+    /// Another registry package installed under this name, written
+    /// `name@npm:other@range`.
+    Alias(Box<PackageSpec>),
+
+    /// A git repository, with an optional commit, branch, tag or semver range
+    /// after `#`.
+    Git {
+        /// The repository URL or hosted shorthand.
+        url: String,
+
+        /// What to check out.
+        committish: Option<String>,
+    },
+
+    /// A local tarball.
+    File(PathBuf),
+
+    /// A local directory.
+    Directory(PathBuf),
+
+    /// A tarball URL.
+    Remote(String),
+}
+
+/// An error parsing a package specifier.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpecError {
+    /// The specifier that failed to parse.
+    pub spec: String,
+
+    /// Why it is invalid.
+    pub reason: String,
+}
+
+impl fmt::Display for SpecError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid package spec `{}`: {}", self.spec, self.reason)
+    }
+}
+
+impl error::Error for SpecError {}
+
+impl PackageSpec {
+    /// Parse a specifier like `pkg`, `@scope/pkg@^1.2`, `alias@npm:pkg@1`,
+    /// `user/repo#main`, `./dir` or `https://example.com/pkg.tgz`.
+    ///
+    /// A name without a version means the `latest` tag.
+    pub fn parse(raw: &str) -> Result<Self, SpecError> {
+        let error = |reason: String| SpecError {
+            spec: raw.to_string(),
+            reason,
+        };
+
+        // Paths, URLs and git shorthands don't start with a name.
+        if let Some(kind) = unnamed_kind(raw) {
+            return Ok(PackageSpec {
+                name: None,
+                raw: raw.to_string(),
+                kind,
+            });
+        }
+
+        let at = match raw.strip_prefix('@') {
+            Some(rest) => rest.find('@').map(|at| at + 1),
+            None => raw.find('@'),
+        };
+        let (name, spec) = match at {
+            Some(at) => (&raw[..at], raw[at + 1..].trim()),
+            None => (raw, ""),
+        };
+
+        validate_name(name).map_err(error)?;
+        let kind = spec_kind(spec).map_err(error)?;
+        Ok(PackageSpec {
+            name: Some(name.to_string()),
+            raw: raw.to_string(),
+            kind,
+        })
+    }
+
+    /// Whether the package comes from a registry.
+    pub fn is_registry(&self) -> bool {
+        match self.kind {
+            SpecKind::Version(_) | SpecKind::Range(_) | SpecKind::Tag(_) => true,
+            SpecKind::Alias(ref target) => target.is_registry(),
+            _ => false,
+        }
+    }
+
+    /// The name the package has in the registry, which for an alias is the
+    /// name of its target.
+    pub fn registry_name(&self) -> Option<&str> {
+        match self.kind {
+            SpecKind::Alias(ref target) => target.registry_name(),
+            _ if self.is_registry() => self.name.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// The scope of the registry package, without its `@`.
+    pub fn scope(&self) -> Option<&str> {
+        let name = self.registry_name()?.strip_prefix('@')?;
+        name.split('/').next()
+    }
+
+    /// The registry name escaped for use in a URL, like `@scope%2fpkg`.
+    pub fn escaped_name(&self) -> Option<String> {
+        self.registry_name().map(|name| name.replace('/', "%2f"))
+    }
+
+    // The exact version, without any `v` or `=` in front.
+    fn version(&self) -> Option<&str> {
+        match self.kind {
+            SpecKind::Version(ref version) => {
+                Some(version.trim_start_matches('=').trim_start_matches('v'))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for PackageSpec {
+    type Err = SpecError;
+
+    fn from_str(raw: &str) -> Result<Self, Self::Err> {
+        PackageSpec::parse(raw)
+    }
+}
+
+impl fmt::Display for PackageSpec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.raw)
+    }
+}
+
+/// Where a registry package is fetched from, returned by `Npmrc::resolve`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Resolved {
+    /// The package's name in the registry.
+    pub name: String,
+
+    /// The registry the package is fetched from.
+    pub registry: String,
+
+    /// The URL of the package's metadata document.
+    pub packument_url: String,
+
+    /// The URL of the tarball, when the spec names an exact version.
+    pub tarball_url: Option<String>,
+
+    /// The credentials npm would send with the metadata request.
+    pub credentials: Option<RegistryCredentials>,
+}
+
+impl Npmrc {
+    /// Work out where a registry package comes from.
+    ///
+    /// Scoped packages use their scope's registry when one is configured, and
+    /// aliases resolve to their target. Returns `None` for git, file,
+    /// directory and tarball URL specs, which don't use a registry.
+    pub fn resolve(&self, spec: &PackageSpec) -> Option<Resolved> {
+        let name = spec.registry_name()?;
+        let registry = self.registry_for(spec)?;
+        let packument_url = registry.packument(name);
+        let tarball_url = match spec.kind {
+            SpecKind::Alias(ref target) => target.version(),
+            _ => spec.version(),
+        }
+        .map(|version| registry.tarball(name, version));
+
+        Some(Resolved {
+            name: name.to_string(),
+            registry: registry.url().to_string(),
+            credentials: self.credentials_for(&packument_url),
+            packument_url,
+            tarball_url,
+        })
+    }
+}
+
+// The kind of a specifier that has no name in front of it.
+fn unnamed_kind(raw: &str) -> Option<SpecKind> {
+    if is_path(raw) || is_url(raw) || is_git(raw) {
+        return spec_kind(raw).ok();
+    }
+    if let Some(kind) = github_shorthand(raw) {
+        return Some(kind);
+    }
+    // Like npm, anything else with a slash or named like a tarball before
+    // any `@` is a relative path rather than a package name.
+    let name = raw.split('@').next().unwrap_or(raw);
+    if !raw.starts_with('@') && (name.contains(['/', '\\']) || is_tarball(name)) {
+        return Some(path(raw));
+    }
+    None
+}
+
+// `user/repo`, optionally with a `#committish`.
+fn github_shorthand(spec: &str) -> Option<SpecKind> {
+    let repo = spec.split('#').next().unwrap_or(spec);
+    let mut parts = repo.splitn(2, '/');
+    match (parts.next(), parts.next()) {
+        (Some(user), Some(name))
+            if !user.is_empty()
+                && !name.is_empty()
+                && !spec.starts_with(['@', '.', '-'])
+                && !repo.contains(['@', ':', '%', ' '])
+                && !name.contains('/')
+                && !is_tarball(name) =>
+        {
+            Some(git(spec))
+        }
+        _ => None,
+    }
+}
+
+// The kind of the part after `name@`.
+fn spec_kind(spec: &str) -> Result<SpecKind, String> {
+    if let Some(target) = spec.strip_prefix("npm:") {
+        let target = PackageSpec::parse(target).map_err(|err| err.reason)?;
+        if !target.is_registry() || matches!(target.kind, SpecKind::Alias(_)) {
+            return Err("aliases must point to a registry package".to_string());
+        }
+        return Ok(SpecKind::Alias(Box::new(target)));
+    }
+
+    if is_path(spec) {
+        return Ok(path(spec));
+    }
+
+    if is_git(spec) || (is_url(spec) && spec.split('#').next().unwrap_or("").ends_with(".git")) {
+        return Ok(git(spec));
+    }
+
+    if is_url(spec) {
+        if spec.starts_with("http://") || spec.starts_with("https://") {
+            return Ok(SpecKind::Remote(spec.to_string()));
+        }
+        return Err(format!("unsupported URL type `{}`", spec));
+    }
+
+    if let Some(kind) = github_shorthand(spec) {
+        return Ok(kind);
+    }
+    if spec.contains(['/', '\\']) || is_tarball(spec) {
+        return Ok(path(spec));
+    }
+
+    if spec.is_empty() {
+        return Ok(SpecKind::Tag("latest".to_string()));
+    }
+    if is_version(spec) {
+        return Ok(SpecKind::Version(spec.to_string()));
+    }
+    if is_range(spec) {
+        return Ok(SpecKind::Range(spec.to_string()));
+    }
+    if is_uri_safe(spec) {
+        return Ok(SpecKind::Tag(spec.to_string()));
+    }
+    Err(format!("`{}` is not a valid version, range or tag", spec))
+}
+
+fn git(spec: &str) -> SpecKind {
+    let (url, committish) = match spec.find('#') {
+        Some(hash) => (&spec[..hash], Some(spec[hash + 1..].to_string())),
+        None => (spec, None),
+    };
+    SpecKind::Git {
+        url: url.to_string(),
+        committish: committish.filter(|committish| !committish.is_empty()),
+    }
+}
+
+// A tarball or directory, by the file name it ends with.
+fn path(spec: &str) -> SpecKind {
+    let path = PathBuf::from(spec.strip_prefix("file:").unwrap_or(spec));
+    if is_tarball(spec) {
+        SpecKind::File(path)
+    } else {
+        SpecKind::Directory(path)
+    }
+}
+
+fn is_path(spec: &str) -> bool {
+    let bytes = spec.as_bytes();
+    let windows_drive = bytes.len() > 2
+        && bytes[0].is_ascii_alphabetic()
+        && bytes[1] == b':'
+        && (bytes[2] == b'\\' || bytes[2] == b'/');
+    spec.starts_with("file:")
+        || spec.starts_with('.')
+        || spec.starts_with('/')
+        || spec.starts_with('~')
+        || windows_drive
+}
+
+fn is_tarball(spec: &str) -> bool {
+    let spec = spec.to_ascii_lowercase();
+    [".tgz", ".tar.gz", ".tar"]
+        .iter()
+        .any(|extension| spec.ends_with(extension))
+}
+
+fn is_url(spec: &str) -> bool {
+    match spec.find("://") {
+        Some(end) => {
+            let scheme = &spec[..end];
+            !scheme.is_empty()
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-' || c == '.')
+        }
+        None => false,
+    }
+}
+
+fn is_git(spec: &str) -> bool {
+    spec.starts_with("git+")
+        || spec.starts_with("git://")
+        || spec.starts_with("git@")
+        || HOSTED_GIT.iter().any(|prefix| spec.starts_with(prefix))
+}
+
+// Check a name the way npm does for packages it can install, which is
+// looser than what it allows for new packages.
+fn validate_name(name: &str) -> Result<(), String> {
+    if name.is_empty() {
+        return Err("package name is empty".to_string());
+    }
+    if name.trim() != name {
+        return Err("package name has surrounding spaces".to_string());
+    }
+    if name.starts_with('.') || name.starts_with('_') {
+        return Err("package name can't start with `.` or `_`".to_string());
+    }
+    if name == "node_modules" || name == "favicon.ico" {
+        return Err(format!("`{}` is not a valid package name", name));
+    }
+
+    let unscoped = match name.strip_prefix('@') {
+        Some(scoped) => match scoped.split_once('/') {
+            Some((scope, package)) if !scope.is_empty() && !package.is_empty() => {
+                if !is_uri_safe(scope) {
+                    return Err("scope contains characters that aren't URL-safe".to_string());
+                }
+                package
+            }
+            _ => return Err("scoped package names look like `@scope/name`".to_string()),
+        },
+        None => name,
+    };
+
+    if unscoped.starts_with('.') || unscoped.starts_with('_') {
+        return Err("package name can't start with `.` or `_`".to_string());
+    }
+    if !is_uri_safe(unscoped) {
+        return Err("package name contains characters that aren't URL-safe".to_string());
+    }
+    Ok(())
+}
+
+// Whether `encodeURIComponent` would leave the text as it is.
+fn is_uri_safe(text: &str) -> bool {
+    text.chars()
+        .all(|c| c.is_ascii_alphanumeric() || "-_.!~*'()".contains(c))
+}
+
+// An exact semver version, with an optional `v` or `=` in front.
+fn is_version(spec: &str) -> bool {
+    let spec = spec.trim_start_matches('=').trim_start_matches('v');
+    let (version, build) = split_off(spec, '+');
+    let (version, prerelease) = split_off(version, '-');
+    let parts: Vec<&str> = version.split('.').collect();
+    parts.len() == 3
+        && parts.iter().all(|part| is_number(part))
+        && prerelease.is_none_or(is_identifiers)
+        && build.is_none_or(is_identifiers)
+}
+
+// A semver range: comparator sets separated by `||`, each made of partial
+// versions with optional operators, or a hyphen range.
+fn is_range(spec: &str) -> bool {
+    spec.split("||").all(|set| {
+        let tokens: Vec<&str> = set.split_whitespace().collect();
+        if tokens.is_empty() {
+            return true;
+        }
+        if let [from, "-", to] = tokens[..] {
+            return is_partial(from) && is_partial(to);
+        }
+
+        let mut operator = false;
+        for token in tokens {
+            let version = token.trim_start_matches(['<', '>', '=', '^', '~']);
+            if version.is_empty() {
+                // An operator followed by a space, like `>= 1.2`.
+                if operator {
+                    return false;
+                }
+                operator = true;
+                continue;
+            }
+            if !is_partial(version) {
+                return false;
+            }
+            operator = false;
+        }
+        !operator
+    })
+}
+
+// A version where trailing parts may be missing or wildcards, like `1.x`.
+fn is_partial(version: &str) -> bool {
+    let version = version.trim_start_matches('=').trim_start_matches('v');
+    let (version, build) = split_off(version, '+');
+    let (version, prerelease) = split_off(version, '-');
+    let parts: Vec<&str> = version.split('.').collect();
+    parts.len() <= 3
+        && parts
+            .iter()
+            .all(|part| is_number(part) || *part == "x" || *part == "X" || *part == "*")
+        && prerelease.is_none_or(is_identifiers)
+        && build.is_none_or(is_identifiers)
+}
+
+fn split_off(text: &str, separator: char) -> (&str, Option<&str>) {
+    match text.split_once(separator) {
+        Some((head, tail)) => (head, Some(tail)),
+        None => (text, None),
+    }
+}
+
+fn is_number(part: &str) -> bool {
+    !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit())
+}
+
+// Dot-separated prerelease or build identifiers.
+fn is_identifiers(text: &str) -> bool {
+    text.split('.').all(|identifier| {
+        !identifier.is_empty()
+            && identifier
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-')
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn git(url: &str, committish: Option<&str>) -> SpecKind {
+        SpecKind::Git {
+            url: url.to_string(),
+            committish: committish.map(str::to_string),
+        }
+    }
+
+    fn alias(raw: &str, name: &str, kind: SpecKind) -> SpecKind {
+        SpecKind::Alias(Box::new(PackageSpec {
+            name: Some(name.to_string()),
+            raw: raw.to_string(),
+            kind,
+        }))
+    }
+
+    #[test]
+    fn parses_specs() {
+        use self::SpecKind::*;
+
+        let cases = vec![
+            ("foo", Some("foo"), Tag("latest".into())),
+            ("foo@", Some("foo"), Tag("latest".into())),
+            ("foo@next", Some("foo"), Tag("next".into())),
+            ("foo@1.2.3", Some("foo"), Version("1.2.3".into())),
+            (
+                "foo@v1.2.3-beta.1+build",
+                Some("foo"),
+                Version("v1.2.3-beta.1+build".into()),
+            ),
+            ("foo@^1.2", Some("foo"), Range("^1.2".into())),
+            ("foo@*", Some("foo"), Range("*".into())),
+            (
+                "foo@>= 1.2 <2 || 3.x",
+                Some("foo"),
+                Range(">= 1.2 <2 || 3.x".into()),
+            ),
+            ("foo@1.0.0 - 2", Some("foo"), Range("1.0.0 - 2".into())),
+            ("@scope/pkg", Some("@scope/pkg"), Tag("latest".into())),
+            ("@scope/pkg@^1.2", Some("@scope/pkg"), Range("^1.2".into())),
+            (
+                "foo@npm:@scope/bar@1",
+                Some("foo"),
+                alias("@scope/bar@1", "@scope/bar", Range("1".into())),
+            ),
+            (
+                "foo@npm:bar",
+                Some("foo"),
+                alias("bar", "bar", Tag("latest".into())),
+            ),
+            ("user/repo", None, git("user/repo", None)),
+            ("user/repo#main", None, git("user/repo", Some("main"))),
+            (
+                "github:user/repo#v1.0.0",
+                None,
+                git("github:user/repo", Some("v1.0.0")),
+            ),
+            (
+                "git+https://github.com/user/repo.git#semver:^1",
+                None,
+                git("git+https://github.com/user/repo.git", Some("semver:^1")),
+            ),
+            (
+                "git@github.com:user/repo.git",
+                None,
+                git("git@github.com:user/repo.git", None),
+            ),
+            ("foo@user/repo", Some("foo"), git("user/repo", None)),
+            (
+                "foo@https://example.com/repo.git#main",
+                Some("foo"),
+                git("https://example.com/repo.git", Some("main")),
+            ),
+            ("./dir", None, Directory("./dir".into())),
+            ("file:../dir", None, Directory("../dir".into())),
+            ("C:\\pkg", None, Directory("C:\\pkg".into())),
+            ("lib/pkg", None, git("lib/pkg", None)),
+            (
+                "lib/packages/pkg",
+                None,
+                Directory("lib/packages/pkg".into()),
+            ),
+            ("../pkg.tgz", None, File("../pkg.tgz".into())),
+            ("/tmp/pkg.tar.gz", None, File("/tmp/pkg.tar.gz".into())),
+            ("x.tgz", None, File("x.tgz".into())),
+            ("pkg-1.0.0.tar.gz", None, File("pkg-1.0.0.tar.gz".into())),
+            ("pkg.tar", None, File("pkg.tar".into())),
+            ("PKG.TGZ", None, File("PKG.TGZ".into())),
+            ("dist/pkg.tgz", None, File("dist/pkg.tgz".into())),
+            ("foo@file:./foo.tgz", Some("foo"), File("./foo.tgz".into())),
+            ("foo@foo.tgz", Some("foo"), File("foo.tgz".into())),
+            (
+                "https://example.com/pkg.tgz",
+                None,
+                Remote("https://example.com/pkg.tgz".into()),
+            ),
+            (
+                "foo@https://example.com/foo-1.0.0.tgz",
+                Some("foo"),
+                Remote("https://example.com/foo-1.0.0.tgz".into()),
+            ),
+        ];
+
+        for (raw, name, kind) in cases {
+            let spec = PackageSpec::parse(raw).unwrap_or_else(|err| panic!("{}", err));
+            assert_eq!(spec.name.as_deref(), name, "name of `{}`", raw);
+            assert_eq!(spec.kind, kind, "kind of `{}`", raw);
+            assert_eq!(spec.raw, raw);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_specs() {
+        let cases = [
+            "",
+            "@scope",
+            "@scope/",
+            "@/pkg",
+            "@sc ope/pkg",
+            "@scope/.hidden",
+            "_private",
+            "@scope/_private",
+            "node_modules",
+            "favicon.ico",
+            "foo bar",
+            "foo@not a range!",
+            "foo@npm:./dir",
+            "foo@npm:bar@npm:baz@1",
+            "foo@ftp://example.com/foo.tgz",
+        ];
+        for raw in &cases {
+            assert!(
+                PackageSpec::parse(raw).is_err(),
+                "`{}` should be invalid",
+                raw
+            );
+        }
+    }
+
+    #[test]
+    fn registry_names() {
+        let spec = PackageSpec::parse("foo@npm:@scope/bar@1.0.0").unwrap();
+        assert!(spec.is_registry());
+        assert_eq!(spec.registry_name(), Some("@scope/bar"));
+        assert_eq!(spec.scope(), Some("scope"));
+        assert_eq!(spec.escaped_name().as_deref(), Some("@scope%2fbar"));
+        assert_eq!(spec.version(), None);
+
+        let spec = PackageSpec::parse("./dir").unwrap();
+        assert!(!spec.is_registry());
+        assert_eq!(spec.registry_name(), None);
+    }
+}
@@ -0,0 +1,649 @@
+//! The TLS settings requests to a registry are made with.
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use std::fmt;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use filesystem::FileSystem;
+use {Error, Npmrc};
+
+/// How to set up TLS for requests to a registry, as chosen by
+/// `Npmrc::tls_for`.
+///
+/// This doesn't depend on any TLS library. With the `rustls` feature,
+/// `rustls_client_config` turns it into a `rustls::ClientConfig`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TlsConfig {
+    /// Whether the server's certificate is verified, from `strict-ssl`.
+    pub strict_ssl: bool,
+
+    /// The CAs to trust instead of the platform's, from `cafile` or `ca`.
+    /// Empty to trust the platform's.
+    pub ca: Vec<Certificate>,
+
+    /// The client certificate to present, if one is configured.
+    pub identity: Option<ClientIdentity>,
+}
+
+/// A client certificate chain and its private key, from `certfile` and
+/// `keyfile`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientIdentity {
+    /// The certificate, followed by any intermediates.
+    pub certificates: Vec<Certificate>,
+
+    /// The certificate's private key.
+    pub key: PrivateKey,
+}
+
+/// A DER encoded X.509 certificate.
+///
+/// The subject, issuer and validity are read from it for diagnostics, and
+/// are `None` if it can't be parsed.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Certificate {
+    der: Vec<u8>,
+    subject: Option<String>,
+    issuer: Option<String>,
+    not_before: Option<String>,
+    not_after: Option<String>,
+}
+
+/// A DER encoded private key. Its bytes are redacted from `Debug` output.
+#[derive(Clone, PartialEq, Eq)]
+pub struct PrivateKey {
+    /// The encoding of the key.
+    pub format: KeyFormat,
+
+    /// The key itself.
+    pub der: Vec<u8>,
+}
+
+/// The encodings a PEM private key can be in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyFormat {
+    /// A `PRIVATE KEY` block.
+    Pkcs8,
+
+    /// An `RSA PRIVATE KEY` block.
+    Pkcs1,
+
+    /// An `EC PRIVATE KEY` block.
+    Sec1,
+}
+
+impl Certificate {
+    /// Wrap a DER encoded certificate.
+    pub fn from_der(der: Vec<u8>) -> Self {
+        let summary = summarize(&der);
+        Certificate {
+            subject: summary.as_ref().map(|s| s.subject.clone()),
+            issuer: summary.as_ref().map(|s| s.issuer.clone()),
+            not_before: summary.as_ref().map(|s| s.not_before.clone()),
+            not_after: summary.map(|s| s.not_after),
+            der,
+        }
+    }
+
+    /// The certificate in DER form.
+    pub fn der(&self) -> &[u8] {
+        &self.der
+    }
+
+    /// The certificate as a PEM block.
+    pub fn to_pem(&self) -> String {
+        let encoded = BASE64.encode(&self.der);
+        let mut pem = String::from("-----BEGIN CERTIFICATE-----\n");
+        for line in encoded.as_bytes().chunks(64) {
+            pem.push_str(&String::from_utf8_lossy(line));
+            pem.push('\n');
+        }
+        pem.push_str("-----END CERTIFICATE-----\n");
+        pem
+    }
+
+    /// Who the certificate was issued to, like `CN=Example CA, O=Example`.
+    pub fn subject(&self) -> Option<&str> {
+        self.subject.as_deref()
+    }
+
+    /// Who issued the certificate, in the same form as `subject`.
+    pub fn issuer(&self) -> Option<&str> {
+        self.issuer.as_deref()
+    }
+
+    /// When the certificate becomes valid, like `2024-01-31T12:00:00Z`.
+    pub fn not_before(&self) -> Option<&str> {
+        self.not_before.as_deref()
+    }
+
+    /// When the certificate expires, in the same form as `not_before`.
+    pub fn not_after(&self) -> Option<&str> {
+        self.not_after.as_deref()
+    }
+
+    /// Whether the certificate has expired, going by the system clock.
+    pub fn is_expired(&self) -> bool {
+        self.not_after()
+            .is_some_and(|not_after| not_after < now().as_str())
+    }
+}
+
+impl fmt::Debug for Certificate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Certificate")
+            .field("subject", &self.subject)
+            .field("not_after", &self.not_after)
+            .finish()
+    }
+}
+
+/// Displays as the subject and expiry, like
+/// `CN=Example CA (expires 2030-01-01T00:00:00Z)`.
+impl fmt::Display for Certificate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.subject().unwrap_or("unreadable certificate"))?;
+        match self.not_after() {
+            Some(not_after) if self.is_expired() => write!(f, " (expired {})", not_after),
+            Some(not_after) => write!(f, " (expires {})", not_after),
+            None => Ok(()),
+        }
+    }
+}
+
+impl fmt::Debug for PrivateKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PrivateKey")
+            .field("format", &self.format)
+            .field("der", &"***")
+            .finish()
+    }
+}
+
+impl Npmrc {
+    /// The TLS settings npm would use for a request to `url`.
+    ///
+    /// CAs come from the PEM bundle in `cafile`, or from the inline `ca`
+    /// values when there's no `cafile`, like npm. Inline values may spell
+    /// newlines as `\n`. The client certificate comes from the `certfile` and
+    /// `keyfile` configured for the registry `url` belongs to, falling back
+    /// to the deprecated inline `cert` and `key`.
+    pub fn tls_for(&self, url: &str) -> Result<TlsConfig, Error> {
+        let fs = &*self.context.fs;
+        let ca = match self.cafile() {
+            Some(path) => certificates("cafile", Some(&path), &read(fs, &path)?)?,
+            None => {
+                let mut ca = Vec::new();
+                for pem in self.ca() {
+                    ca.extend(certificates("ca", None, &pem)?);
+                }
+                ca
+            }
+        };
+
+        let credentials = self.credentials_for(url);
+        let files = credentials.as_ref().and_then(|credentials| {
+            match (&credentials.certfile, &credentials.keyfile) {
+                (Some(certfile), Some(keyfile)) => Some((certfile, keyfile)),
+                _ => None,
+            }
+        });
+        let identity = match files {
+            Some((certfile, keyfile)) => Some(ClientIdentity {
+                certificates: certificates("certfile", Some(certfile), &read(fs, certfile)?)?,
+                key: private_key("keyfile", Some(keyfile), &read(fs, keyfile)?)?,
+            }),
+            None => match (self.other.get("cert"), self.other.get("key")) {
+                (Some(cert), Some(key)) => Some(ClientIdentity {
+                    certificates: certificates("cert", None, cert)?,
+                    key: private_key("key", None, key)?,
+                }),
+                _ => None,
+            },
+        };
+
+        Ok(TlsConfig {
+            strict_ssl: self.strict_ssl(),
+            ca,
+            identity,
+        })
+    }
+}
+
+fn read(fs: &dyn FileSystem, path: &Path) -> Result<String, Error> {
+    fs.read_to_string(path).map_err(|err| Error::io(path, err))
+}
+
+// Every `CERTIFICATE` block in `pem`, of which there has to be at least one.
+fn certificates(key: &str, path: Option<&Path>, pem: &str) -> Result<Vec<Certificate>, Error> {
+    let blocks = pem_blocks(pem).map_err(|message| Error::pem(key, path, message))?;
+    let certificates: Vec<Certificate> = blocks
+        .into_iter()
+        .filter(|(label, _)| label == "CERTIFICATE")
+        .map(|(_, der)| Certificate::from_der(der))
+        .collect();
+    if certificates.is_empty() {
+        return Err(Error::pem(key, path, "no certificates found"));
+    }
+    Ok(certificates)
+}
+
+// The first private key block in `pem`.
+fn private_key(key: &str, path: Option<&Path>, pem: &str) -> Result<PrivateKey, Error> {
+    let blocks = pem_blocks(pem).map_err(|message| Error::pem(key, path, message))?;
+    for (label, der) in blocks {
+        let format = match label.as_str() {
+            "PRIVATE KEY" => KeyFormat::Pkcs8,
+            "RSA PRIVATE KEY" => KeyFormat::Pkcs1,
+            "EC PRIVATE KEY" => KeyFormat::Sec1,
+            "ENCRYPTED PRIVATE KEY" => {
+                return Err(Error::pem(
+                    key,
+                    path,
+                    "encrypted private keys aren't supported",
+                ))
+            }
+            _ => continue,
+        };
+        return Ok(PrivateKey { format, der });
+    }
+    Err(Error::pem(key, path, "no private key found"))
+}
+
+// The label and decoded contents of every block in `pem`. Text outside of
+// blocks is ignored.
+fn pem_blocks(pem: &str) -> Result<Vec<(String, Vec<u8>)>, &'static str> {
+    let pem = pem.replace("\\n", "\n");
+    let mut blocks = Vec::new();
+    let mut rest = pem.as_str();
+
+    while let Some(start) = rest.find("-----BEGIN ") {
+        rest = &rest[start + "-----BEGIN ".len()..];
+        let label_end = rest.find("-----").ok_or("unterminated BEGIN line")?;
+        let label = rest[..label_end].to_string();
+        rest = &rest[label_end + "-----".len()..];
+
+        let end_marker = format!("-----END {}-----", label);
+        let end = rest.find(&end_marker).ok_or("missing END line")?;
+        let body: String = rest[..end].chars().filter(|c| !c.is_whitespace()).collect();
+        let der = BASE64.decode(body).map_err(|_| "invalid base64")?;
+        blocks.push((label, der));
+        rest = &rest[end + end_marker.len()..];
+    }
+
+    Ok(blocks)
+}
+
+struct Summary {
+    subject: String,
+    issuer: String,
+    not_before: String,
+    not_after: String,
+}
+
+// Read the names and validity out of a certificate:
+//
+// Certificate ::= SEQUENCE { tbsCertificate, signatureAlgorithm, signature }
+// TBSCertificate ::= SEQUENCE { [0] version OPTIONAL, serialNumber,
+//     signature, issuer, validity, subject, ... }
+fn summarize(der: &[u8]) -> Option<Summary> {
+    let (_, certificate) = Der(der).expect(SEQUENCE)?;
+    let (_, tbs) = Der(certificate).expect(SEQUENCE)?;
+    let mut tbs = Der(tbs);
+
+    if tbs.peek() == Some(0xa0) {
+        tbs.next()?;
+    }
+    tbs.next()?; // serialNumber
+    tbs.next()?; // signature
+    let issuer = tbs.expect(SEQUENCE)?.1;
+    let validity = tbs.expect(SEQUENCE)?.1;
+    let subject = tbs.expect(SEQUENCE)?.1;
+
+    let mut validity = Der(validity);
+    Some(Summary {
+        subject: name(subject)?,
+        issuer: name(issuer)?,
+        not_before: time(validity.next()?)?,
+        not_after: time(validity.next()?)?,
+    })
+}
+
+const SEQUENCE: u8 = 0x30;
+const SET: u8 = 0x31;
+const OID: u8 = 0x06;
+
+// A reader of consecutive DER values.
+struct Der<'a>(&'a [u8]);
+
+impl<'a> Der<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.0.first().cloned()
+    }
+
+    // The next value's tag and contents.
+    fn next(&mut self) -> Option<(u8, &'a [u8])> {
+        let (&tag, rest) = self.0.split_first()?;
+        let (&first, rest) = rest.split_first()?;
+        let (len, rest) = if first < 0x80 {
+            (first as usize, rest)
+        } else {
+            let count = (first & 0x7f) as usize;
+            if count == 0 || count > 4 || rest.len() < count {
+                return None;
+            }
+            let len = rest[..count]
+                .iter()
+                .fold(0, |len, &byte| (len << 8) | byte as usize);
+            (len, &rest[count..])
+        };
+        if rest.len() < len {
+            return None;
+        }
+        self.0 = &rest[len..];
+        Some((tag, &rest[..len]))
+    }
+
+    fn expect(&mut self, tag: u8) -> Option<(u8, &'a [u8])> {
+        self.next().filter(|&(found, _)| found == tag)
+    }
+}
+
+// A distinguished name like `CN=Example CA, O=Example`.
+fn name(der: &[u8]) -> Option<String> {
+    let mut parts = Vec::new();
+    let mut rdns = Der(der);
+    while rdns.peek().is_some() {
+        let mut attributes = Der(rdns.expect(SET)?.1);
+        while attributes.peek().is_some() {
+            let mut attribute = Der(attributes.expect(SEQUENCE)?.1);
+            let oid = oid(attribute.expect(OID)?.1)?;
+            let value = string(attribute.next()?)?;
+            let label = match oid.as_str() {
+                "2.5.4.3" => "CN",
+                "2.5.4.6" => "C",
+                "2.5.4.7" => "L",
+                "2.5.4.8" => "ST",
+                "2.5.4.10" => "O",
+                "2.5.4.11" => "OU",
+                "1.2.840.113549.1.9.1" => "emailAddress",
+                _ => oid.as_str(),
+            };
+            parts.push(format!("{}={}", label, value));
+        }
+    }
+    Some(parts.join(", "))
+}
+
+// An object identifier in dotted form.
+fn oid(der: &[u8]) -> Option<String> {
+    let (&first, rest) = der.split_first()?;
+    let mut arcs = vec![u64::from(first / 40), u64::from(first % 40)];
+    let mut arc: u64 = 0;
+    for &byte in rest {
+        arc = arc.checked_mul(128)? | u64::from(byte & 0x7f);
+        if byte & 0x80 == 0 {
+            arcs.push(arc);
+            arc = 0;
+        }
+    }
+    let arcs: Vec<String> = arcs.iter().map(u64::to_string).collect();
+    Some(arcs.join("."))
+}
+
+// The text of a directory string.
+fn string((tag, der): (u8, &[u8])) -> Option<String> {
+    match tag {
+        // UTF8String, PrintableString, IA5String
+        0x0c | 0x13 | 0x16 => String::from_utf8(der.to_vec()).ok(),
+        // TeletexString, which tools often fill with UTF-8, else Latin-1.
+        0x14 => Some(
+            String::from_utf8(der.to_vec())
+                .unwrap_or_else(|_| der.iter().map(|&byte| byte as char).collect()),
+        ),
+        // BMPString
+        0x1e => {
+            let units: Vec<u16> = der
+                .chunks(2)
+                .map(|pair| u16::from_be_bytes([pair[0], *pair.get(1).unwrap_or(&0)]))
+                .collect();
+            String::from_utf16(&units).ok()
+        }
+        _ => None,
+    }
+}
+
+// A UTCTime or GeneralizedTime as `YYYY-MM-DDTHH:MM:SSZ`.
+fn time((tag, der): (u8, &[u8])) -> Option<String> {
+    let text = ::std::str::from_utf8(der).ok()?;
+    let digits = text.strip_suffix('Z')?;
+    // Checked before slicing, which panics inside a multibyte character.
+    if !digits.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    let full = match tag {
+        // UTCTime years 50 to 99 are in the 1900s.
+        0x17 if digits.len() == 12 => {
+            let century = if &digits[..2] >= "50" { "19" } else { "20" };
+            format!("{}{}", century, digits)
+        }
+        0x18 if digits.len() == 14 => digits.to_string(),
+        _ => return None,
+    };
+    Some(format!(
+        "{}-{}-{}T{}:{}:{}Z",
+        &full[..4],
+        &full[4..6],
+        &full[6..8],
+        &full[8..10],
+        &full[10..12],
+        &full[12..14]
+    ))
+}
+
+// The current time in the form `time` produces.
+fn now() -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0);
+    let (days, seconds) = (seconds / 86_400, seconds % 86_400);
+
+    // Days since 1970-01-01 to a civil date, after Howard Hinnant's
+    // `civil_from_days`.
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds % 3600 / 60,
+        seconds % 60
+    )
+}
+
+#[cfg(feature = "rustls")]
+mod rustls_config {
+    use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+    use rustls::crypto::{self, CryptoProvider};
+    use rustls::pki_types::{
+        CertificateDer, PrivateKeyDer, PrivatePkcs1KeyDer, PrivatePkcs8KeyDer, PrivateSec1KeyDer,
+        ServerName, UnixTime,
+    };
+    use rustls::{ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme};
+    use std::sync::Arc;
+    use webpki_roots;
+
+    use super::{KeyFormat, TlsConfig};
+
+    impl TlsConfig {
+        /// Build a rustls `ClientConfig` using the `ring` crypto provider.
+        ///
+        /// Without any `ca`, the Mozilla roots from `webpki-roots` are
+        /// trusted. With `strict_ssl` off, any server certificate is
+        /// accepted.
+        pub fn rustls_client_config(&self) -> Result<ClientConfig, rustls::Error> {
+            let provider = Arc::new(crypto::ring::default_provider());
+            let builder = ClientConfig::builder_with_provider(provider.clone())
+                .with_safe_default_protocol_versions()?;
+
+            let builder = if self.strict_ssl {
+                let mut roots = RootCertStore::empty();
+                if self.ca.is_empty() {
+                    roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+                } else {
+                    for certificate in &self.ca {
+                        roots.add(CertificateDer::from(certificate.der().to_vec()))?;
+                    }
+                }
+                builder.with_root_certificates(roots)
+            } else {
+                builder
+                    .dangerous()
+                    .with_custom_certificate_verifier(Arc::new(AcceptAny(provider)))
+            };
+
+            match self.identity {
+                Some(ref identity) => {
+                    let chain = identity
+                        .certificates
+                        .iter()
+                        .map(|certificate| CertificateDer::from(certificate.der().to_vec()))
+                        .collect();
+                    let der = identity.key.der.clone();
+                    let key = match identity.key.format {
+                        KeyFormat::Pkcs8 => PrivateKeyDer::from(PrivatePkcs8KeyDer::from(der)),
+                        KeyFormat::Pkcs1 => PrivateKeyDer::from(PrivatePkcs1KeyDer::from(der)),
+                        KeyFormat::Sec1 => PrivateKeyDer::from(PrivateSec1KeyDer::from(der)),
+                    };
+                    builder.with_client_auth_cert(chain, key)
+                }
+                None => Ok(builder.with_no_client_auth()),
+            }
+        }
+    }
+
+    // What `strict-ssl=false` asks for: any certificate is accepted, but
+    // handshake signatures are still checked.
+    #[derive(Debug)]
+    struct AcceptAny(Arc<CryptoProvider>);
+
+    impl ServerCertVerifier for AcceptAny {
+        fn verify_server_cert(
+            &self,
+            _end_entity: &CertificateDer,
+            _intermediates: &[CertificateDer],
+            _server_name: &ServerName,
+            _ocsp_response: &[u8],
+            _now: UnixTime,
+        ) -> Result<ServerCertVerified, rustls::Error> {
+            Ok(ServerCertVerified::assertion())
+        }
+
+        fn verify_tls12_signature(
+            &self,
+            message: &[u8],
+            cert: &CertificateDer,
+            dss: &DigitallySignedStruct,
+        ) -> Result<HandshakeSignatureValid, rustls::Error> {
+            let algorithms = &self.0.signature_verification_algorithms;
+            crypto::verify_tls12_signature(message, cert, dss, algorithms)
+        }
+
+        fn verify_tls13_signature(
+            &self,
+            message: &[u8],
+            cert: &CertificateDer,
+            dss: &DigitallySignedStruct,
+        ) -> Result<HandshakeSignatureValid, rustls::Error> {
+            let algorithms = &self.0.signature_verification_algorithms;
+            crypto::verify_tls13_signature(message, cert, dss, algorithms)
+        }
+
+        fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+            self.0.signature_verification_algorithms.supported_schemes()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A self-signed P-256 certificate, valid from 2024 to 2034.
+    const CERTIFICATE: &str = "\
+-----BEGIN CERTIFICATE-----\n\
+MIIBpTCCAUugAwIBAgIUdLSrvnKAhW/FAbfoYMLFJbrbMDIwCgYIKoZIzj0EAwIw\n\
+KDEWMBQGA1UEAwwNbnBtcmMgdGVzdCBDQTEOMAwGA1UECgwFbnBtcmMwHhcNMjQw\n\
+MTAxMDAwMDAwWhcNMzQwMTAxMDAwMDAwWjAoMRYwFAYDVQQDDA1ucG1yYyB0ZXN0\n\
+IENBMQ4wDAYDVQQKDAVucG1yYzBZMBMGByqGSM49AgEGCCqGSM49AwEHA0IABDHs\n\
+z2x/kimT3ZHTh6rIJpq4UXVEID+JRfsDeq8FzH0yCYlKLWdTv9BW98k8mjPp68mF\n\
+XBKwWgDbU/EIyWz8SZGjUzBRMB0GA1UdDgQWBBRk6t7pxbfkW+AGedH1YBIVojVO\n\
+tDAfBgNVHSMEGDAWgBRk6t7pxbfkW+AGedH1YBIVojVOtDAPBgNVHRMBAf8EBTAD\n\
+AQH/MAoGCCqGSM49BAMCA0gAMEUCIQDQ3TfLW3SkuviTqQFjDCUYYPmi1965IPVz\n\
+9D6i2R5tkwIgNfMIBOOSgpWkF5ueYaWQ8dkrtNjO5B+pX+qHWya9y5E=\n\
+-----END CERTIFICATE-----\n";
+
+    #[test]
+    fn reads_certificates() {
+        let parsed = certificates("ca", None, CERTIFICATE).unwrap();
+        assert_eq!(parsed.len(), 1);
+        let certificate = &parsed[0];
+        assert_eq!(certificate.subject(), Some("CN=npmrc test CA, O=npmrc"));
+        assert_eq!(certificate.issuer(), certificate.subject());
+        assert_eq!(certificate.not_before(), Some("2024-01-01T00:00:00Z"));
+        assert_eq!(certificate.not_after(), Some("2034-01-01T00:00:00Z"));
+        assert_eq!(certificate.to_pem(), CERTIFICATE);
+
+        // `.npmrc` values spell newlines as `\n`.
+        let escaped = CERTIFICATE.replace('\n', "\\n");
+        assert_eq!(certificates("ca", None, &escaped).unwrap(), parsed);
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        assert!(certificates("ca", None, "").is_err());
+        assert!(certificates("ca", None, "-----BEGIN CERTIFICATE-----\nMIIB").is_err());
+        assert!(certificates("ca", None, &CERTIFICATE.replace("MIIB", "M!IB")).is_err());
+        assert!(private_key("key", None, CERTIFICATE).is_err());
+
+        let certificate = Certificate::from_der(b"not a certificate".to_vec());
+        assert_eq!(certificate.subject(), None);
+        assert_eq!(certificate.not_after(), None);
+        assert!(!certificate.is_expired());
+    }
+
+    #[test]
+    fn rejects_malformed_times() {
+        assert_eq!(
+            time((0x17, b"240101000000Z")).as_deref(),
+            Some("2024-01-01T00:00:00Z")
+        );
+        assert_eq!(
+            time((0x17, b"990101000000Z")).as_deref(),
+            Some("1999-01-01T00:00:00Z")
+        );
+        assert_eq!(
+            time((0x18, b"20500101000000Z")).as_deref(),
+            Some("2050-01-01T00:00:00Z")
+        );
+        // Twelve bytes, with a multibyte character across the century digits.
+        assert_eq!(time((0x17, "2\u{e9}010100000Z".as_bytes())), None);
+        assert_eq!(time((0x17, b"24010100000aZ")), None);
+        assert_eq!(time((0x17, b"240101000000")), None);
+        assert_eq!(time((0x18, b"240101000000Z")), None);
+    }
+}